@@ -0,0 +1,24 @@
+use web3::ethabi::{self, Contract, Function};
+
+// The ABIs are bundled into the library so it doesn't depend on the working directory
+pub const UNISWAP_V1_EXCHANGE: &str = include_str!("../abi/eth/uniswap_v1/exchange.json");
+pub const UNISWAP_V1_FACTORY: &str = include_str!("../abi/eth/uniswap_v1/factory.json");
+pub const UNISWAP_V2_PAIR: &str = include_str!("../abi/eth/uniswap/v2/pair.json");
+pub const UNISWAP_V2_ROUTER02: &str = include_str!("../abi/eth/uniswap/v2/router02.json");
+// SHIB is a plain OpenZeppelin ERC-20, without any extensions
+pub const ERC20: &str = include_str!("../abi/eth/shib.json");
+pub const WETH: &str = include_str!("../abi/eth/weth.json");
+pub const UNISWAP_UNIVERSAL_ROUTER: &str = include_str!("../abi/eth/uniswap/universal_router.json");
+pub const PANCAKESWAP_SMART_ROUTER: &str = include_str!("../abi/eth/pancakeswap/smart_router_v3.json");
+
+pub fn load(abi: &str) -> Result<Contract, ethabi::Error> {
+	Contract::load(abi.as_bytes())
+}
+
+/// Finds the function called by `input`, by matching its first 4 bytes against the function signatures.
+pub fn called_function<'a>(contract: &'a Contract, input: &[u8]) -> Option<&'a Function> {
+	if input.len() < 4 {
+		return None;
+	}
+	contract.functions().find(|function| function.short_signature() == input[..4])
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use web3::types::Address;
+
+use crate::trade::Chain;
+
+/// Version of the address book file format this build reads.
+pub const VERSION: u64 = 1;
+
+#[derive(Debug)]
+pub enum AddressBookError {
+	Io(std::io::Error),
+	/// Malformed JSON, or an address that isn't a 20 byte hex string
+	Json(serde_json::Error),
+	UnsupportedVersion(u64),
+	MalformedChainId(String),
+	/// A symbol referring to a token missing from the chain's `tokens`
+	UnknownToken { chain_id: u64, symbol: String },
+}
+
+impl fmt::Display for AddressBookError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AddressBookError::Io(e) => write!(f, "failed to read address book: {}", e),
+			AddressBookError::Json(e) => write!(f, "malformed address book: {}", e),
+			AddressBookError::UnsupportedVersion(version) => write!(f, "address book version {} is not supported, expected {}", version, VERSION),
+			AddressBookError::MalformedChainId(id) => write!(f, "malformed chain id {}", id),
+			AddressBookError::UnknownToken { chain_id, symbol } => write!(f, "token {} is not in the address book of chain {}", symbol, chain_id),
+		}
+	}
+}
+
+impl std::error::Error for AddressBookError {}
+
+impl From<std::io::Error> for AddressBookError {
+	fn from(e: std::io::Error) -> Self {
+		AddressBookError::Io(e)
+	}
+}
+
+impl From<serde_json::Error> for AddressBookError {
+	fn from(e: serde_json::Error) -> Self {
+		AddressBookError::Json(e)
+	}
+}
+
+/// Known contracts on a single chain, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChainAddresses {
+	/// Short name used to pick the chain, e.g. `eth`
+	pub name: String,
+	/// Symbol of the wrapped native coin (WETH, WBNB) in `tokens`
+	pub wrapped_native: String,
+	/// Tokens by symbol
+	pub tokens: HashMap<String, Address>,
+	/// Routers by the decoder that handles them, e.g. `uniswap-v2-router02`
+	#[serde(default)]
+	pub routers: HashMap<String, Address>,
+	/// Factories by protocol, e.g. `uniswap-v1`
+	#[serde(default)]
+	pub factories: HashMap<String, Address>,
+	/// Pools by protocol, then by the symbol of the token they trade
+	#[serde(default)]
+	pub pools: HashMap<String, HashMap<String, Address>>,
+	/// Human readable names of any address
+	#[serde(default)]
+	pub labels: HashMap<Address, String>,
+}
+
+impl ChainAddresses {
+	pub fn token(&self, symbol: &str) -> Option<Address> {
+		self.tokens.get(symbol).copied()
+	}
+
+	pub fn wrapped_native(&self) -> Address {
+		// Checked when the address book is loaded
+		self.tokens[&self.wrapped_native]
+	}
+
+	pub fn symbol(&self, address: Address) -> Option<&str> {
+		self.tokens.iter()
+			.find(|(_, token)| **token == address)
+			.map(|(symbol, _)| symbol.as_str())
+	}
+
+	/// The token's symbol, the address' label, or the address itself.
+	pub fn describe(&self, address: Address) -> String {
+		match self.symbol(address).or_else(|| self.labels.get(&address).map(String::as_str)) {
+			Some(name) => name.to_owned(),
+			None => format!("{:?}", address),
+		}
+	}
+
+	fn validate(&self, chain_id: u64) -> Result<(), AddressBookError> {
+		let unknown = |symbol: &str| AddressBookError::UnknownToken { chain_id, symbol: symbol.to_owned() };
+		if !self.tokens.contains_key(&self.wrapped_native) {
+			return Err(unknown(&self.wrapped_native));
+		}
+		for pools in self.pools.values() {
+			if let Some(symbol) = pools.keys().find(|symbol| !self.tokens.contains_key(*symbol)) {
+				return Err(unknown(symbol));
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Deserialize)]
+struct AddressBookFile {
+	version: u64,
+	chains: HashMap<String, ChainAddresses>,
+}
+
+/// Tokens, routers, factories, pools and labels of every supported chain, keyed by chain id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressBook {
+	pub chains: HashMap<u64, ChainAddresses>,
+}
+
+impl AddressBook {
+	pub fn load(path: &Path) -> Result<Self, AddressBookError> {
+		Self::from_json(&fs::read_to_string(path)?)
+	}
+
+	pub fn from_json(json: &str) -> Result<Self, AddressBookError> {
+		let file: AddressBookFile = serde_json::from_str(json)?;
+		if file.version != VERSION {
+			return Err(AddressBookError::UnsupportedVersion(file.version));
+		}
+
+		let mut chains: HashMap<u64, ChainAddresses> = HashMap::new();
+		for (id, addresses) in file.chains {
+			let chain_id: u64 = id.parse().map_err(|_| AddressBookError::MalformedChainId(id.clone()))?;
+			addresses.validate(chain_id)?;
+			chains.insert(chain_id, addresses);
+		}
+		Ok(Self { chains })
+	}
+
+	pub fn chain(&self, chain: Chain) -> Option<&ChainAddresses> {
+		self.chains.get(&chain.id())
+	}
+
+	/// Looks up the symbol of a token in the chain's address book.
+	pub fn symbol(&self, chain: Chain, address: Address) -> Option<&str> {
+		self.chain(chain).and_then(|addresses| addresses.symbol(address))
+	}
+}
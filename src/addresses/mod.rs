@@ -1,2 +0,0 @@
-pub mod eth;
-pub mod bsc;
@@ -0,0 +1,122 @@
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+
+use web3::Transport;
+use web3::types::{Transaction, H256};
+
+use crate::decoders::universal_router::Step;
+use crate::fetch::FetchError;
+use crate::tracker::{TrackError, Tracker};
+use crate::trade::Trade;
+
+/// What came of decoding one transaction hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxOutcome {
+	Success { trades: Vec<Trade> },
+	/// Mined in a block that doesn't have enough confirmations to be final yet
+	Unconfirmed { trades: Vec<Trade> },
+	/// Mined in a block a reorg orphaned, so its trades didn't happen after all
+	Retracted { trades: Vec<Trade> },
+	/// Not mined yet, its trades decoded from its input are provisional
+	Pending { trades: Vec<Trade> },
+	/// A pending transaction replaced by another with the same sender and nonce
+	Replaced { by: H256 },
+	/// A pending transaction that left the mempool without being mined
+	Dropped,
+	Reverted,
+	NotFound,
+	/// Mined and successful, but the decoder failed on it
+	Undecodable { error: String },
+	/// Malformed hash or a failed RPC call
+	Error { error: String },
+}
+
+impl TxOutcome {
+	pub fn status(&self) -> &'static str {
+		match self {
+			TxOutcome::Success { .. } => "success",
+			TxOutcome::Unconfirmed { .. } => "unconfirmed",
+			TxOutcome::Retracted { .. } => "retracted",
+			TxOutcome::Pending { .. } => "pending",
+			TxOutcome::Replaced { .. } => "replaced",
+			TxOutcome::Dropped => "dropped",
+			TxOutcome::Reverted => "reverted",
+			TxOutcome::NotFound => "not_found",
+			TxOutcome::Undecodable { .. } => "undecodable",
+			TxOutcome::Error { .. } => "error",
+		}
+	}
+
+	/// The trades the outcome carries, if any.
+	pub fn trades(&self) -> &[Trade] {
+		match self {
+			TxOutcome::Success { trades }
+			| TxOutcome::Unconfirmed { trades }
+			| TxOutcome::Retracted { trades }
+			| TxOutcome::Pending { trades } => trades,
+			_ => &[],
+		}
+	}
+
+	/// The outcome of decoding `tx` into `trades`, pending if it isn't mined.
+	pub fn decoded(tx: &Transaction, trades: Vec<Trade>) -> Self {
+		match tx.block_number {
+			Some(_) => TxOutcome::Success { trades },
+			None => TxOutcome::Pending { trades },
+		}
+	}
+}
+
+impl From<Result<Vec<Trade>, TrackError>> for TxOutcome {
+	fn from(result: Result<Vec<Trade>, TrackError>) -> Self {
+		match result {
+			Ok(trades) => TxOutcome::Success { trades },
+			Err(TrackError::Fetch(FetchError::Pending(_))) => TxOutcome::Pending { trades: Vec::new() },
+			Err(TrackError::Fetch(FetchError::Reverted(_))) => TxOutcome::Reverted,
+			Err(TrackError::Fetch(FetchError::NotFound(_))) => TxOutcome::NotFound,
+			Err(TrackError::Decode(e)) => TxOutcome::Undecodable { error: e.to_string() },
+			Err(e) => TxOutcome::Error { error: e.to_string() },
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchResult {
+	pub hash: String,
+	#[serde(flatten)]
+	pub outcome: TxOutcome,
+	/// Block the transaction was mined in, if it was
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub block_number: Option<u64>,
+	/// Commands of a Universal Router call
+	#[serde(skip)]
+	pub steps: Vec<Step>,
+}
+
+/// Hashes listed one per line, skipping blank lines and `#` comments.
+pub fn parse_hashes(text: &str) -> Vec<String> {
+	text.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(str::to_owned)
+		.collect()
+}
+
+/// Decodes many transactions, at most `concurrency` at a time, yielding one result per hash
+/// in the order the hashes were given. A failing hash doesn't stop the others.
+pub fn decode_batch<T: Transport>(tracker: &Tracker<T>, hashes: Vec<String>, concurrency: usize) -> impl Stream<Item = BatchResult> + '_ {
+	stream::iter(hashes)
+		.map(move |hash| async move {
+			match tracker.decode_hash(&hash).await {
+				Ok((tx, trades)) => BatchResult {
+					hash,
+					outcome: TxOutcome::decoded(&tx, trades),
+					block_number: tx.block_number.map(|number| number.as_u64()),
+					steps: tracker.steps(&tx),
+				},
+				Err(e) => BatchResult { hash, outcome: Err(e).into(), block_number: None, steps: Vec::new() },
+			}
+		})
+		.buffered(concurrency.max(1))
+}
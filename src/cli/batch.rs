@@ -0,0 +1,30 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use futures::StreamExt;
+
+use web3::transports::Http;
+
+use dex_trade_tracker::batch;
+use dex_trade_tracker::Tracker;
+
+use crate::exit_with;
+use crate::output::Output;
+
+/// Decodes the hashes listed in `file`, or on stdin without one.
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, file: Option<&Path>, concurrency: usize) {
+	let text = match file {
+		Some(file) => fs::read_to_string(file).unwrap_or_else(|e| exit_with(e)),
+		None => {
+			let mut text = String::new();
+			io::stdin().read_to_string(&mut text).unwrap_or_else(|e| exit_with(e));
+			text
+		},
+	};
+
+	let mut results = Box::pin(batch::decode_batch(tracker, batch::parse_hashes(&text), concurrency));
+	while let Some(result) = results.next().await {
+		output.tx_in_block(&result.hash, &result.outcome, &result.steps, result.block_number).await;
+	}
+}
@@ -0,0 +1,39 @@
+use web3::transports::Http;
+
+use dex_trade_tracker::batch::TxOutcome;
+use dex_trade_tracker::export::TxRecord;
+use dex_trade_tracker::{fetch_tx, Tracker};
+
+use crate::exit_with;
+use crate::output::{Format, Output};
+
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, hash: &str) {
+	if output.format != Format::Text {
+		let (outcome, steps, block_number) = match tracker.decode_hash(hash).await {
+			Ok((tx, trades)) => (TxOutcome::decoded(&tx, trades), tracker.steps(&tx), tx.block_number.map(|number| number.as_u64())),
+			Err(e) => (Err(e).into(), Vec::new(), None),
+		};
+		output.tx_in_block(hash, &outcome, &steps, block_number).await;
+		return;
+	}
+
+	let (tx, receipt) = fetch_tx(&tracker.web3, hash).await.unwrap_or_else(|e| exit_with(e));
+	println!("Caller: {:?}", tx.from);
+
+	let trades = match &receipt {
+		Some(receipt) => tracker.decode(&tx, receipt).await,
+		None => {
+			println!("Pending, the trades are provisional");
+			tracker.decode_pending(&tx).await
+		},
+	};
+	let trades = trades.unwrap_or_else(|e| exit_with(e));
+	let outcome = TxOutcome::decoded(&tx, trades);
+	let record = TxRecord::new(hash, &outcome, &tracker.steps(&tx)).mined_in(tx.block_number.map(|number| number.as_u64()));
+	output.save(&record, outcome.trades()).await;
+	match tracker.decoder_name(&tx) {
+		Some(name) => println!("Using {}", name),
+		None => println!("Unknown contract, using token flows"),
+	}
+	output.sentences(outcome.trades()).await;
+}
@@ -0,0 +1,29 @@
+use web3::transports::Http;
+use web3::types::Address;
+
+use dex_trade_tracker::{history, LogScanner, Tracker};
+
+use crate::exit_with;
+use crate::output::Output;
+
+/// Decodes the trades `wallet` made in `from_block..=to_block`, oldest first. Its transactions
+/// are found with `eth_getLogs`, `window` blocks at a time.
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, wallet: Address, from_block: u64, to_block: Option<u64>, window: u64) {
+	let to_block = match to_block {
+		Some(to_block) => to_block,
+		None => tracker.web3.eth().block_number().await.unwrap_or_else(|e| exit_with(e)).as_u64(),
+	};
+	if to_block < from_block {
+		exit_with(format!("block {} comes after block {}", from_block, to_block));
+	}
+
+	let mut scanner = LogScanner::wallet(window, wallet);
+	let mut start = from_block;
+	while start <= to_block {
+		let range = scanner.next(&tracker.web3, start, to_block).await.unwrap_or_else(|e| exit_with(e));
+		for tx in &history::decode_wallet_txs(tracker, wallet, &range.tx_hashes).await {
+			output.wallet_tx(tx).await;
+		}
+		start = range.to_block + 1;
+	}
+}
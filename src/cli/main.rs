@@ -0,0 +1,180 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use web3::transports::Http;
+use web3::types::Address;
+
+use dex_trade_tracker::{AddressBook, Chain, Config, Store, TokenResolver, Tracker};
+
+use crate::output::{Format, Output};
+
+mod batch;
+mod decode;
+mod history;
+mod mempool;
+mod output;
+mod scan;
+mod serve;
+mod watch;
+
+/// Decodes DEX trades from transactions on Ethereum and BSC.
+#[derive(Parser)]
+#[command(name = "dex-trade-tracker", version)]
+struct Cli {
+	/// Chain to track, by name or chain id: eth, bsc, 1, 56
+	#[arg(long, global = true, default_value = "eth")]
+	chain: Chain,
+	/// Config file with the RPC providers, address book and policies
+	#[arg(long, global = true, default_value = "./config/config.json")]
+	config: PathBuf,
+	/// How decoded transactions are written
+	#[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+	output: Format,
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Decodes the trades of a mined transaction
+	Decode {
+		/// Transaction hash
+		hash: String,
+	},
+	/// Decodes the trades of many transactions, listed one hash per line
+	Batch {
+		/// File listing the hashes, stdin when unset
+		#[arg(long)]
+		file: Option<PathBuf>,
+		/// Most transactions fetched at once
+		#[arg(long, default_value_t = 8)]
+		concurrency: usize,
+	},
+	/// Decodes the trades of every router-bound transaction in a range of blocks
+	Scan {
+		#[arg(long)]
+		from_block: u64,
+		/// Last block to scan, the latest block when unset
+		#[arg(long)]
+		to_block: Option<u64>,
+		/// Blocks, or with --logs transactions, fetched at once
+		#[arg(long, default_value_t = 4)]
+		concurrency: usize,
+		/// Find swaps with eth_getLogs instead of walking every transaction
+		#[arg(long)]
+		logs: bool,
+		/// Blocks per eth_getLogs query with --logs, halved when the provider rejects a query
+		#[arg(long, default_value_t = 2000)]
+		window: u64,
+		/// Name the scan's checkpoint and failed blocks are stored under, so a later scan with
+		/// the same job resumes it. Defaults to the range, e.g. scan-17000000-17000010, or
+		/// scan-logs-17000000-latest with --logs and no --to-block
+		#[arg(long)]
+		job: Option<String>,
+	},
+	/// Decodes the trades a wallet made in a range of blocks, oldest first
+	History {
+		/// Wallet address
+		address: Address,
+		#[arg(long)]
+		from_block: u64,
+		/// Last block to search, the latest block when unset
+		#[arg(long)]
+		to_block: Option<u64>,
+		/// Blocks per eth_getLogs query, halved when the provider rejects a query
+		#[arg(long, default_value_t = 2000)]
+		window: u64,
+	},
+	/// Decodes the trades of new blocks as they're mined
+	Watch {
+		/// Seconds between checks for new blocks, when the config sets no WebSocket provider
+		#[arg(long, default_value_t = 5)]
+		poll_interval: u64,
+	},
+	/// Decodes router-bound transactions as they enter the mempool, then writes whether each
+	/// was confirmed, replaced or dropped
+	Mempool {
+		/// Most pending transactions fetched at once
+		#[arg(long, default_value_t = 16)]
+		concurrency: usize,
+	},
+	/// Serves a webhook that decodes the transactions posted to it, on every chain with an
+	/// RPC provider, and responds with their trades as JSON
+	Serve {
+		/// Most transactions of a request fetched at once
+		#[arg(long, default_value_t = 8)]
+		concurrency: usize,
+	},
+}
+
+/// Prints the error and exits.
+pub fn exit_with(e: impl fmt::Display) -> ! {
+	eprintln!("Error: {}", e);
+	std::process::exit(1);
+}
+
+#[tokio::main]
+async fn main() {
+	let cli = Cli::parse();
+	if cli.output == Format::Json && matches!(cli.command, Command::Watch { .. } | Command::Mempool { .. }) {
+		exit_with("json output is printed once the run ends, use --output ndjson to watch");
+	}
+
+	let config = Config::load(&cli.config).unwrap_or_else(|e| exit_with(e));
+	let address_book = AddressBook::load(&config.address_book).unwrap_or_else(|e| exit_with(e));
+	let store = config.store.as_ref().map(|path| {
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir).unwrap_or_else(|e| exit_with(e));
+		}
+		Store::open(path).unwrap_or_else(|e| exit_with(e))
+	});
+	if let Command::Serve { concurrency } = cli.command {
+		serve::run(&config, &address_book, cli.chain, store, concurrency).await;
+		return;
+	}
+
+	let rpc_provider_url = config.rpc_provider_url(cli.chain)
+		.unwrap_or_else(|| exit_with(format!("{}-rpc-provider-url is not set in {}", cli.chain, cli.config.display())));
+
+	let transport = Http::new(rpc_provider_url).unwrap_or_else(|e| exit_with(e));
+	let web3 = web3::Web3::new(transport);
+	let tracker = Tracker::new(cli.chain, web3.clone(), &address_book, config.policy(cli.chain)).unwrap_or_else(|e| exit_with(e));
+	if let Some(from_block) = config.uniswap_v1_index_from_block {
+		if let Err(e) = tracker.index_uniswap_v1(from_block).await {
+			eprintln!("Error: {}", e);
+		}
+	}
+	let resolver = TokenResolver::new(web3, cli.chain, &config.token_cache_dir).unwrap_or_else(|e| exit_with(e));
+	let mut output = Output::new(cli.output, resolver, tracker.addresses.clone());
+	if let Some(store) = store {
+		output = output.with_store(store, cli.chain);
+	}
+
+	match cli.command {
+		Command::Decode { hash } => decode::run(&tracker, &mut output, &hash).await,
+		Command::Batch { file, concurrency } => batch::run(&tracker, &mut output, file.as_deref(), concurrency).await,
+		Command::Scan { from_block, to_block, concurrency, logs, window, job } => {
+			let job = job.unwrap_or_else(|| {
+				let to_block = to_block.map_or_else(|| "latest".to_owned(), |to_block| to_block.to_string());
+				format!("{}-{}-{}", if logs { "scan-logs" } else { "scan" }, from_block, to_block)
+			});
+			scan::run(&tracker, &mut output, from_block, to_block, concurrency, logs.then_some(window), &job).await
+		},
+		Command::History { address, from_block, to_block, window } => {
+			history::run(&tracker, &mut output, address, from_block, to_block, window).await
+		},
+		Command::Watch { poll_interval } => {
+			watch::run(&tracker, &mut output, config.ws_provider_url(cli.chain), poll_interval, config.confirmations(cli.chain)).await
+		},
+		Command::Mempool { concurrency } => {
+			let ws_provider_url = config.ws_provider_url(cli.chain)
+				.unwrap_or_else(|| exit_with(format!("{}-ws-provider-url is not set in {}", cli.chain, cli.config.display())));
+			mempool::run(&tracker, &mut output, ws_provider_url, concurrency).await
+		},
+		Command::Serve { .. } => unreachable!(),
+	}
+	output.finish();
+}
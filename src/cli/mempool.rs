@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+
+use web3::transports::Http;
+use web3::types::{Transaction, TransactionId, U64};
+
+use dex_trade_tracker::batch::TxOutcome;
+use dex_trade_tracker::fetch::fetch_block_txs;
+use dex_trade_tracker::{follow, FetchError, FollowError, Mempool, Settled, TrackError, Tracker};
+
+use crate::output::Output;
+
+/// How long a pending transaction may go unsettled before the provider is asked whether it's
+/// still in the mempool
+const DROP_CHECK_AGE: Duration = Duration::from_secs(300);
+
+enum Notification {
+	Head(Result<u64, FollowError>),
+	/// A transaction entering the mempool, `None` if the provider no longer has it
+	Pending(Result<Option<Box<Transaction>>, String>),
+}
+
+/// Decodes router-bound transactions as they enter the mempool into provisional trades, then
+/// follows new blocks to write whether each was confirmed, replaced or dropped.
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, ws_provider_url: &str, concurrency: usize) {
+	let heads = follow::new_heads(ws_provider_url.to_owned(), follow::RECONNECT_DELAY, follow::IDLE_TIMEOUT)
+		.filter_map(|head| async move {
+			match head {
+				Ok(head) => head.number.map(|number| Notification::Head(Ok(number.as_u64()))),
+				Err(e) => Some(Notification::Head(Err(e))),
+			}
+		});
+	let pending = follow::pending_transactions(ws_provider_url.to_owned(), follow::RECONNECT_DELAY, follow::IDLE_TIMEOUT)
+		.map(|hash| async move {
+			let hash = hash.map_err(|e| e.to_string())?;
+			tracker.web3.eth().transaction(TransactionId::Hash(hash)).await.map(|tx| tx.map(Box::new)).map_err(|e| e.to_string())
+		})
+		.buffer_unordered(concurrency.max(1))
+		.map(Notification::Pending);
+
+	let mut mempool = Mempool::new();
+	let mut next_block: Option<u64> = None;
+	let mut notifications = Box::pin(stream::select(heads, pending));
+	while let Some(notification) = notifications.next().await {
+		match notification {
+			Notification::Pending(Ok(Some(tx))) => add(tracker, output, &mut mempool, *tx).await,
+			Notification::Pending(Ok(None)) => continue,
+			Notification::Head(Ok(number)) => {
+				let mut block = next_block.unwrap_or(number);
+				while block <= number {
+					match fetch_block_txs(&tracker.web3, block).await {
+						Ok(txs) => settle(tracker, output, &mut mempool, &txs).await,
+						Err(e) => {
+							eprintln!("Error: {}", e);
+							break;
+						},
+					}
+					block += 1;
+				}
+				next_block = Some(block);
+				check_dropped(tracker, output, &mut mempool).await;
+			},
+			Notification::Pending(Err(e)) => eprintln!("Error: {}, reconnecting", e),
+			Notification::Head(Err(e)) => eprintln!("Error: {}, reconnecting", e),
+		}
+	}
+}
+
+/// Follows a pending transaction if it's sent to a contract with a decoder and trades, looking
+/// up the Uniswap V1 exchange it may call first.
+async fn add(tracker: &Tracker<Http>, output: &mut Output, mempool: &mut Mempool, tx: Transaction) {
+	if tx.block_number.is_some() || mempool.contains(tx.hash) {
+		return;
+	}
+	tracker.discover(&tx).await;
+	if tracker.decoder_name(&tx).is_none() {
+		return;
+	}
+	let trades = match tracker.decode_pending(&tx).await {
+		Ok(trades) if !trades.is_empty() => trades,
+		Ok(_) | Err(TrackError::Decode(_)) => return,
+		Err(e) => {
+			eprintln!("Error: {}", e);
+			return;
+		},
+	};
+
+	let hash = tx.hash;
+	output.tx(&format!("{:?}", hash), &TxOutcome::Pending { trades: trades.clone() }, &[]).await;
+	if let Some(replaced) = mempool.add(tx, trades) {
+		output.tx(&format!("{:?}", replaced.tx.hash), &TxOutcome::Replaced { by: hash }, &[]).await;
+	}
+}
+
+/// Writes what came of the pending transactions a block mined or replaced.
+async fn settle(tracker: &Tracker<Http>, output: &mut Output, mempool: &mut Mempool, block_txs: &[Transaction]) {
+	for (pending, settled) in mempool.mined(block_txs) {
+		let (outcome, block_number) = match settled {
+			Settled::Mined(tx) => (confirm(tracker, &tx).await, tx.block_number.map(|number| number.as_u64())),
+			Settled::Replaced { by } => (TxOutcome::Replaced { by }, None),
+		};
+		output.tx_in_block(&format!("{:?}", pending.tx.hash), &outcome, &[], block_number).await;
+	}
+}
+
+/// Decodes a mined transaction with its receipt, so its trades have what was executed.
+async fn confirm(tracker: &Tracker<Http>, tx: &Transaction) -> TxOutcome {
+	let receipt = match tracker.web3.eth().transaction_receipt(tx.hash).await {
+		Ok(Some(receipt)) => receipt,
+		Ok(None) => return TxOutcome::NotFound,
+		Err(e) => return Err(TrackError::from(FetchError::from(e))).into(),
+	};
+	if receipt.status != Some(U64::from(1)) {
+		return TxOutcome::Reverted;
+	}
+	tracker.decode(tx, &receipt).await.into()
+}
+
+/// Asks the provider about transactions that have been pending a while, writing those it
+/// dropped and those mined in blocks missed while reconnecting.
+async fn check_dropped(tracker: &Tracker<Http>, output: &mut Output, mempool: &mut Mempool) {
+	for hash in mempool.stale(DROP_CHECK_AGE) {
+		let (outcome, block_number) = match tracker.web3.eth().transaction(TransactionId::Hash(hash)).await {
+			Ok(Some(tx)) if tx.block_number.is_none() => {
+				mempool.touch(hash);
+				continue;
+			},
+			Ok(Some(tx)) => (confirm(tracker, &tx).await, tx.block_number.map(|number| number.as_u64())),
+			Ok(None) => (TxOutcome::Dropped, None),
+			Err(e) => {
+				eprintln!("Error: {}", e);
+				continue;
+			},
+		};
+		mempool.remove(hash);
+		output.tx_in_block(&format!("{:?}", hash), &outcome, &[], block_number).await;
+	}
+}
@@ -0,0 +1,220 @@
+use clap::ValueEnum;
+
+use web3::transports::Http;
+use web3::types::{Address, H256};
+
+use dex_trade_tracker::batch::TxOutcome;
+use dex_trade_tracker::decoders::universal_router::Step;
+use dex_trade_tracker::export::{self, TxRecord};
+use dex_trade_tracker::{Asset, Chain, ChainAddresses, Store, TokenMetadata, TokenResolver, Trade, WalletTx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+	/// Human readable sentences
+	Text,
+	/// A single JSON array of transactions, printed once everything is decoded
+	Json,
+	/// One JSON transaction per line
+	Ndjson,
+	/// One row per trade leg
+	Csv,
+}
+
+/// Writes decoded transactions to stdout in the chosen format, and to the store if there is one.
+pub struct Output {
+	pub format: Format,
+	resolver: TokenResolver<Http>,
+	/// Names the tokens along a trade's route
+	addresses: ChainAddresses,
+	store: Option<(Store, Chain)>,
+	/// Held back until `finish` for JSON
+	records: Vec<TxRecord>,
+	wrote_csv_header: bool,
+}
+
+impl Output {
+	pub fn new(format: Format, resolver: TokenResolver<Http>, addresses: ChainAddresses) -> Self {
+		Self {
+			format,
+			resolver,
+			addresses,
+			store: None,
+			records: Vec::new(),
+			wrote_csv_header: false,
+		}
+	}
+
+	/// Also stores everything written, as decoded on `chain`.
+	pub fn with_store(mut self, store: Store, chain: Chain) -> Self {
+		self.store = Some((store, chain));
+		self
+	}
+
+	/// The store, and the chain everything is stored as decoded on.
+	pub fn store(&self) -> Option<(&Store, Chain)> {
+		self.store.as_ref().map(|(store, chain)| (store, *chain))
+	}
+
+	/// Stores a decoded transaction and the metadata of the tokens it trades, see [`save`].
+	pub async fn save(&mut self, record: &TxRecord, trades: &[Trade]) {
+		if let Some((store, chain)) = &mut self.store {
+			let tokens = resolve_tokens(&mut self.resolver, trades).await;
+			save(store, *chain, record, &tokens);
+		}
+	}
+
+	/// Stores that a block was processed.
+	pub fn save_block(&mut self, number: u64, hash: H256, timestamp: u64) {
+		if let Some((store, chain)) = &self.store {
+			if let Err(e) = store.insert_block(*chain, number, hash, Some(timestamp)) {
+				eprintln!("Error: {}", e);
+			}
+		}
+	}
+
+	/// Prints each trade as a sentence with human amounts, followed by its V3 route and its
+	/// policy violations.
+	pub async fn sentences(&mut self, trades: &[Trade]) {
+		for trade in trades {
+			match self.resolver.describe(trade).await {
+				Ok(description) => println!("{}", description),
+				Err(e) => eprintln!("Error: {}", e),
+			}
+			if let Some(route) = &trade.route {
+				println!("Route: {}", route.describe(&self.addresses));
+			}
+			for violation in trade.verdict.iter().flat_map(|verdict| &verdict.violations) {
+				println!("Policy violation: {}", violation);
+			}
+		}
+	}
+
+	/// Writes what came of decoding one transaction that isn't mined, or whose block is unknown.
+	pub async fn tx(&mut self, hash: &str, outcome: &TxOutcome, steps: &[Step]) {
+		self.tx_in_block(hash, outcome, steps, None).await;
+	}
+
+	/// Writes what came of decoding one transaction, mined in `block_number` if that's known.
+	pub async fn tx_in_block(&mut self, hash: &str, outcome: &TxOutcome, steps: &[Step], block_number: Option<u64>) {
+		let record = TxRecord::new(hash, outcome, steps).mined_in(block_number);
+		self.save(&record, outcome.trades()).await;
+
+		if self.format == Format::Text {
+			match outcome {
+				TxOutcome::Success { trades } => {
+					println!("{}: {} trade(s)", hash, trades.len());
+					self.sentences(trades).await;
+				},
+				TxOutcome::Unconfirmed { trades } => {
+					println!("{}: unconfirmed, {} trade(s)", hash, trades.len());
+					self.sentences(trades).await;
+				},
+				TxOutcome::Retracted { trades } => println!("{}: retracted by a reorg, {} trade(s) undone", hash, trades.len()),
+				TxOutcome::Pending { trades } => {
+					println!("{}: pending, {} provisional trade(s)", hash, trades.len());
+					self.sentences(trades).await;
+				},
+				TxOutcome::Replaced { by } => println!("{}: replaced by {:?}", hash, by),
+				TxOutcome::Undecodable { error } | TxOutcome::Error { error } => println!("{}: {}, {}", hash, outcome.status(), error),
+				_ => println!("{}: {}", hash, outcome.status()),
+			}
+			return;
+		}
+
+		self.record(record);
+	}
+
+	/// Writes a transaction of a wallet's history, with its block and time when it was fetched.
+	pub async fn wallet_tx(&mut self, tx: &WalletTx) {
+		let hash = format!("{:?}", tx.hash);
+		let (block_number, timestamp) = match (tx.block_number, tx.timestamp) {
+			(Some(block_number), Some(timestamp)) => (block_number, timestamp),
+			_ => return self.tx(&hash, &tx.outcome, &[]).await,
+		};
+		let trades = tx.outcome.trades();
+		let record = TxRecord::new(&hash, &tx.outcome, &[]).at_block(block_number, timestamp);
+		self.save(&record, trades).await;
+
+		if self.format == Format::Text {
+			println!("{} block {} {}: {} trade(s)", export::format_timestamp(timestamp), block_number, hash, trades.len());
+			self.sentences(trades).await;
+			return;
+		}
+		self.record(record);
+	}
+
+	/// Writes a record in one of the structured formats.
+	fn record(&mut self, record: TxRecord) {
+		match self.format {
+			Format::Json => self.records.push(record),
+			Format::Ndjson => println!("{}", serde_json::to_string(&record).unwrap()),
+			Format::Csv => {
+				if !self.wrote_csv_header {
+					println!("{}", export::CSV_HEADER);
+					self.wrote_csv_header = true;
+				}
+				for row in export::csv_rows(&record) {
+					println!("{}", row);
+				}
+			},
+			Format::Text => {},
+		}
+	}
+
+	/// Writes trades from many transactions of a block, grouped by transaction.
+	pub async fn trades(&mut self, trades: Vec<Trade>, block_number: Option<u64>) {
+		self.grouped_trades(trades, block_number, |trades| TxOutcome::Success { trades }).await;
+	}
+
+	/// Writes trades from many transactions of a block, grouped by transaction into the outcome
+	/// `outcome` makes.
+	pub async fn grouped_trades(&mut self, trades: Vec<Trade>, block_number: Option<u64>, outcome: fn(Vec<Trade>) -> TxOutcome) {
+		let mut trades = trades.into_iter().peekable();
+		while let Some(first) = trades.next() {
+			let hash = first.tx_hash;
+			let mut tx_trades = vec![first];
+			while let Some(trade) = trades.next_if(|trade| trade.tx_hash == hash) {
+				tx_trades.push(trade);
+			}
+			self.tx_in_block(&format!("{:?}", hash), &outcome(tx_trades), &[], block_number).await;
+		}
+	}
+
+	/// Prints whatever is held back until the end.
+	pub fn finish(&mut self) {
+		if self.format == Format::Json {
+			println!("{}", serde_json::to_string_pretty(&self.records).unwrap());
+			self.records.clear();
+		}
+	}
+}
+
+/// The metadata of the tokens `trades` trade, to store with them. Tokens that can't be resolved
+/// are left out, the trades are stored regardless.
+pub async fn resolve_tokens(resolver: &mut TokenResolver<Http>, trades: &[Trade]) -> Vec<(Address, TokenMetadata)> {
+	let mut tokens = Vec::new();
+	for token in trades.iter().flat_map(|trade| [trade.token_in, trade.token_out]) {
+		let address = match token {
+			Asset::Token(address) => address,
+			Asset::Native => continue,
+		};
+		if let Ok(metadata) = resolver.resolve(address).await {
+			tokens.push((address, metadata));
+		}
+	}
+	tokens
+}
+
+/// Stores a decoded transaction and the metadata of the tokens it trades, as
+/// [`resolve_tokens`] found them. Failing to doesn't stop what's being run, so errors are
+/// only reported.
+pub fn save(store: &mut Store, chain: Chain, record: &TxRecord, tokens: &[(Address, TokenMetadata)]) {
+	if let Err(e) = store.insert_tx(chain, record) {
+		eprintln!("Error: {}", e);
+	}
+	for (address, metadata) in tokens {
+		if let Err(e) = store.insert_token(chain, *address, metadata) {
+			eprintln!("Error: {}", e);
+		}
+	}
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use futures::{stream, StreamExt};
+
+use web3::transports::Http;
+
+use dex_trade_tracker::batch::{self, TxOutcome};
+use dex_trade_tracker::{FailedRange, LogScanner, Tracker};
+
+use crate::exit_with;
+use crate::output::Output;
+
+/// How often progress is reported on stderr
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Counts what a scan has gone through, reported on stderr so stdout stays parseable.
+struct Progress {
+	from_block: u64,
+	total: u64,
+	start: Instant,
+	last_report: Instant,
+	blocks: u64,
+	/// Transactions looked at, every transaction of a block when walking blocks
+	txs: usize,
+	/// Transactions decoded
+	matched: usize,
+	trades: usize,
+}
+
+impl Progress {
+	fn new(from_block: u64, to_block: u64) -> Self {
+		let now = Instant::now();
+		Self { from_block, total: to_block - from_block + 1, start: now, last_report: now, blocks: 0, txs: 0, matched: 0, trades: 0 }
+	}
+
+	/// Records that the scan got through `block`, reporting at most every `PROGRESS_INTERVAL`.
+	fn advance(&mut self, block: u64) {
+		self.blocks = block - self.from_block + 1;
+		if self.last_report.elapsed() < PROGRESS_INTERVAL {
+			return;
+		}
+		self.last_report = Instant::now();
+		let elapsed = self.start.elapsed().as_secs_f64();
+		eprintln!(
+			"Block {} ({:.1}%): {} of {} txs decoded, {} trade(s), {:.1} blocks/s, {:.1} txs/s",
+			block,
+			self.blocks as f64 * 100.0 / self.total as f64,
+			self.matched,
+			self.txs,
+			self.trades,
+			self.blocks as f64 / elapsed,
+			self.txs as f64 / elapsed,
+		);
+	}
+
+	fn finish(&self) {
+		let elapsed = self.start.elapsed().as_secs_f64();
+		eprintln!(
+			"Scanned {} block(s) in {:.1}s: {} of {} txs decoded, {} trade(s), {:.1} blocks/s",
+			self.blocks,
+			elapsed,
+			self.matched,
+			self.txs,
+			self.trades,
+			self.blocks as f64 / elapsed.max(f64::EPSILON),
+		);
+	}
+}
+
+/// A scan job's progress, checkpointed in the store when there is one so a later run with the
+/// same job resumes where this one stopped.
+struct Job {
+	name: String,
+	/// Whether processed blocks move the job's checkpoint, which retries of failed ranges don't
+	checkpoints: bool,
+	/// Attempts failures are recorded with
+	attempts: u32,
+	/// Blocks that failed since the last success, merged while they're adjacent
+	failure: Option<FailedRange>,
+}
+
+impl Job {
+	fn new(name: &str, checkpoints: bool, attempts: u32) -> Self {
+		Self { name: name.to_owned(), checkpoints, attempts, failure: None }
+	}
+
+	/// Records that `from_block..=to_block` failed, to be retried by a later run of the job.
+	fn fail(&mut self, output: &Output, from_block: u64, to_block: u64, error: impl fmt::Display) {
+		eprintln!("Error: blocks {} to {} failed: {}", from_block, to_block, error);
+		match &mut self.failure {
+			Some(failure) if failure.to_block + 1 == from_block => {
+				failure.to_block = to_block;
+				failure.error = error.to_string();
+			},
+			_ => {
+				self.flush(output);
+				self.failure = Some(FailedRange { from_block, to_block, error: error.to_string(), attempts: self.attempts });
+			},
+		}
+	}
+
+	/// Stores the failed blocks not stored yet. Without a store they were only reported.
+	fn flush(&mut self, output: &Output) {
+		if let (Some(failure), Some((store, chain))) = (self.failure.take(), output.store()) {
+			if let Err(e) = store.insert_failed_range(chain, &self.name, &failure) {
+				eprintln!("Error: {}", e);
+			}
+		}
+	}
+
+	/// Records that everything through `block` was processed or stored as failed.
+	fn done(&mut self, output: &Output, block: u64) {
+		self.flush(output);
+		if let (true, Some((store, chain))) = (self.checkpoints, output.store()) {
+			if let Err(e) = store.set_checkpoint(chain, &self.name, block) {
+				eprintln!("Error: {}", e);
+			}
+		}
+	}
+}
+
+/// Decodes the trades in `from_block..=to_block`. Walks every block, fetching up to
+/// `concurrency` at once, or with `window` set finds swaps with `eth_getLogs` that many
+/// blocks at a time.
+///
+/// With a store, the scan is the job `job`: it first retries the blocks in the range the job
+/// failed to process before, then resumes after the job's checkpoint if it falls in the range,
+/// and records the blocks it fails to process instead of stopping.
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, from_block: u64, to_block: Option<u64>, concurrency: usize, window: Option<u64>, job: &str) {
+	let to_block = match to_block {
+		Some(to_block) => to_block,
+		None => tracker.web3.eth().block_number().await.unwrap_or_else(|e| exit_with(e)).as_u64(),
+	};
+	if to_block < from_block {
+		exit_with(format!("block {} comes after block {}", from_block, to_block));
+	}
+
+	let (checkpoint, failed) = match output.store() {
+		Some((store, chain)) => (
+			store.checkpoint(chain, job).unwrap_or_else(|e| exit_with(e)),
+			store.failed_ranges(chain, job).unwrap_or_else(|e| exit_with(e))
+				.into_iter()
+				.filter(|range| range.from_block <= to_block && range.to_block >= from_block)
+				.collect(),
+		),
+		None => (None, Vec::new()),
+	};
+
+	for range in failed {
+		eprintln!("Retrying blocks {} to {}, failed {} time(s): {}", range.from_block, range.to_block, range.attempts, range.error);
+		let mut retry = Job::new(job, false, range.attempts + 1);
+		let mut progress = Progress::new(range.from_block, range.to_block);
+		scan(tracker, output, &mut retry, &mut progress, range.to_block, concurrency, window).await;
+		if let Some((store, chain)) = output.store() {
+			if let Err(e) = store.remove_failed_range(chain, job, range.from_block, range.to_block) {
+				eprintln!("Error: {}", e);
+			}
+		}
+		retry.flush(output);
+		progress.finish();
+	}
+
+	let from_block = match checkpoint {
+		// A checkpoint outside the range was left by the job scanning other blocks
+		Some(checkpoint) if (from_block..=to_block).contains(&checkpoint) => {
+			eprintln!("Resuming job {} after block {}", job, checkpoint);
+			checkpoint + 1
+		},
+		_ => from_block,
+	};
+	if from_block > to_block {
+		return;
+	}
+	let mut job = Job::new(job, true, 1);
+	let mut progress = Progress::new(from_block, to_block);
+	scan(tracker, output, &mut job, &mut progress, to_block, concurrency, window).await;
+	job.done(output, to_block);
+	progress.finish();
+}
+
+async fn scan(tracker: &Tracker<Http>, output: &mut Output, job: &mut Job, progress: &mut Progress, to_block: u64, concurrency: usize, window: Option<u64>) {
+	match window {
+		Some(window) => scan_logs(tracker, output, job, progress, to_block, concurrency, window).await,
+		None => scan_blocks(tracker, output, job, progress, to_block, concurrency).await,
+	}
+}
+
+/// Walks every transaction of every block, decoding those sent to a contract with a decoder.
+async fn scan_blocks(tracker: &Tracker<Http>, output: &mut Output, job: &mut Job, progress: &mut Progress, to_block: u64, concurrency: usize) {
+	// `buffered` keeps the blocks in order however their fetches interleave
+	let mut results = stream::iter(progress.from_block..=to_block)
+		.map(|number| async move { (number, tracker.decode_block(number).await) })
+		.buffered(concurrency.max(1));
+	while let Some((number, result)) = results.next().await {
+		let block = match result {
+			Ok(block) => block,
+			Err(e) => {
+				job.fail(output, number, number, e);
+				continue;
+			},
+		};
+		progress.txs += block.tx_count;
+		progress.matched += block.matched;
+		progress.trades += block.trades.len();
+		output.save_block(block.number, block.hash, block.timestamp);
+		output.trades(block.trades, Some(block.number)).await;
+		job.done(output, block.number);
+		progress.advance(block.number);
+	}
+}
+
+/// Decodes only the transactions that emitted swap events, whichever contract they were sent to.
+async fn scan_logs(tracker: &Tracker<Http>, output: &mut Output, job: &mut Job, progress: &mut Progress, to_block: u64, concurrency: usize, window: u64) {
+	let mut scanner = LogScanner::swaps(window);
+	let mut start = progress.from_block;
+	while start <= to_block {
+		let range = match scanner.next(&tracker.web3, start, to_block).await {
+			Ok(range) => range,
+			Err(e) => {
+				let end = to_block.min(start.saturating_add(scanner.window() - 1));
+				job.fail(output, start, end, e);
+				start = end + 1;
+				continue;
+			},
+		};
+
+		let hashes = range.tx_hashes.iter().map(|hash| format!("{:?}", hash)).collect();
+		let mut results = Box::pin(batch::decode_batch(tracker, hashes, concurrency));
+		let mut error: Option<String> = None;
+		while let Some(result) = results.next().await {
+			progress.txs += 1;
+			match &result.outcome {
+				TxOutcome::Success { trades } => {
+					progress.matched += 1;
+					progress.trades += trades.len();
+				},
+				// The provider failing on a transaction leaves the window to retry
+				TxOutcome::Error { error: e } => error = Some(format!("{}: {}", result.hash, e)),
+				_ => {},
+			}
+			output.tx_in_block(&result.hash, &result.outcome, &result.steps, result.block_number).await;
+		}
+		match error {
+			Some(error) => job.fail(output, range.from_block, range.to_block, error),
+			None => job.done(output, range.to_block),
+		}
+		progress.advance(range.to_block);
+		start = range.to_block + 1;
+	}
+}
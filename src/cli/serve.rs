@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+use futures::lock::Mutex as AsyncMutex;
+use serde::Serialize;
+use warp::http::StatusCode;
+use warp::reply::{self, Reply, Response};
+use warp::Filter;
+
+use web3::transports::Http;
+use web3::types::Transaction;
+
+use dex_trade_tracker::batch::TxOutcome;
+use dex_trade_tracker::export::TxRecord;
+use dex_trade_tracker::{AddressBook, Chain, Config, Store, TokenResolver, Tracker};
+
+use crate::exit_with;
+use crate::output;
+
+/// Decodes the transactions posted for one chain.
+struct ChainDecoder {
+	tracker: Tracker<Http>,
+	resolver: AsyncMutex<TokenResolver<Http>>,
+}
+
+struct Server {
+	chains: HashMap<Chain, ChainDecoder>,
+	store: Option<AsyncMutex<Store>>,
+	/// Most transactions of a request decoded at once
+	concurrency: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+	error: String,
+}
+
+fn error(status: StatusCode, error: impl ToString) -> Response {
+	reply::with_status(reply::json(&ErrorBody { error: error.to_string() }), status).into_response()
+}
+
+/// Decodes a JSON array of transactions and responds with what was decoded from each, in order.
+async fn transactions(server: Arc<Server>, chain: String, body: Bytes) -> Result<Response, Infallible> {
+	let chain: Chain = match chain.parse() {
+		Ok(chain) => chain,
+		Err(e) => return Ok(error(StatusCode::NOT_FOUND, e)),
+	};
+	let decoder = match server.chains.get(&chain) {
+		Some(decoder) => decoder,
+		None => return Ok(error(StatusCode::NOT_FOUND, format!("no rpc provider is set for {}", chain))),
+	};
+	let txs: Vec<Transaction> = match serde_json::from_slice(&body) {
+		Ok(txs) => txs,
+		Err(e) => return Ok(error(StatusCode::BAD_REQUEST, format!("malformed transactions: {}", e))),
+	};
+
+	let records: Vec<TxRecord> = stream::iter(txs)
+		.map(|tx| decode(&server, chain, decoder, tx))
+		.buffered(server.concurrency.max(1))
+		.collect()
+		.await;
+	Ok(reply::json(&records).into_response())
+}
+
+/// Decodes a posted transaction, with the receipt of a mined one, and stores the result.
+async fn decode(server: &Server, chain: Chain, decoder: &ChainDecoder, tx: Transaction) -> TxRecord {
+	let outcome = match decoder.tracker.decode_tx(&tx).await {
+		Ok(trades) => TxOutcome::decoded(&tx, trades),
+		Err(e) => Err(e).into(),
+	};
+	let record = TxRecord::new(&format!("{:?}", tx.hash), &outcome, &decoder.tracker.steps(&tx)).mined_in(tx.block_number.map(|number| number.as_u64()));
+	if let Some(store) = &server.store {
+		// Tokens are resolved first, so one transaction's token lookups don't hold up storing others
+		let tokens = output::resolve_tokens(&mut *decoder.resolver.lock().await, outcome.trades()).await;
+		output::save(&mut *store.lock().await, chain, &record, &tokens);
+	}
+	record
+}
+
+/// Serves the webhook for every chain with an RPC provider in the config. Transactions are
+/// posted to `/v1/<chain>/transactions`, or to `/` for `default_chain`, and at most
+/// `concurrency` of a request are decoded at once.
+pub async fn run(config: &Config, address_book: &AddressBook, default_chain: Chain, store: Option<Store>, concurrency: usize) {
+	let mut chains = HashMap::new();
+	for chain in Chain::ALL {
+		let rpc_provider_url = match config.rpc_provider_url(chain) {
+			Some(url) => url,
+			None => continue,
+		};
+		let web3 = web3::Web3::new(Http::new(rpc_provider_url).unwrap_or_else(|e| exit_with(e)));
+		let tracker = match Tracker::new(chain, web3.clone(), address_book, config.policy(chain)) {
+			Ok(tracker) => tracker,
+			Err(e) => {
+				eprintln!("Error: {}", e);
+				continue;
+			},
+		};
+		if let Some(from_block) = config.uniswap_v1_index_from_block {
+			if let Err(e) = tracker.index_uniswap_v1(from_block).await {
+				eprintln!("Error: {}", e);
+			}
+		}
+		let resolver = TokenResolver::new(web3, chain, &config.token_cache_dir).unwrap_or_else(|e| exit_with(e));
+		chains.insert(chain, ChainDecoder { tracker, resolver: AsyncMutex::new(resolver) });
+	}
+	if chains.is_empty() {
+		exit_with("no chain has an rpc provider set in the config");
+	}
+
+	let server = Arc::new(Server { chains, store: store.map(AsyncMutex::new), concurrency });
+	let chain = warp::path!("v1" / String / "transactions")
+		.or(warp::path::end().map(move || default_chain.to_string()))
+		.unify();
+	let routes = warp::post()
+		.and(chain)
+		.and(warp::body::content_length_limit(config.serve.body_limit))
+		.and(warp::body::bytes())
+		.and_then(move |chain: String, body: Bytes| transactions(server.clone(), chain, body));
+
+	let (address, serving) = warp::serve(routes)
+		.try_bind_ephemeral(config.serve.bind_address)
+		.unwrap_or_else(|e| exit_with(e));
+	println!("Server started at http://{}", address);
+	serving.await;
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use web3::transports::Http;
+
+use dex_trade_tracker::batch::TxOutcome;
+use dex_trade_tracker::{follow, ReorgWindow, Tracker};
+
+use crate::output::Output;
+
+/// Decodes new blocks in order as they're mined: on the heads of a WebSocket subscription to
+/// `ws_provider_url` when there is one, otherwise polling for them every `poll_interval` seconds.
+///
+/// Trades are written as unconfirmed when their block comes in and again once it has
+/// `confirmations` confirmations, or as retracted if a reorg orphans it first.
+pub async fn run(tracker: &Tracker<Http>, output: &mut Output, ws_provider_url: Option<&str>, poll_interval: u64, confirmations: u64) {
+	let mut window = ReorgWindow::new(confirmations);
+	match ws_provider_url {
+		Some(url) => subscribe(tracker, output, &mut window, url).await,
+		None => poll(tracker, output, &mut window, poll_interval).await,
+	}
+}
+
+/// Decodes the blocks after the tip of `window` through `latest_block`, starting at
+/// `latest_block` when nothing was followed yet. Stops at the first block that fails so
+/// it's retried.
+async fn decode_blocks(tracker: &Tracker<Http>, output: &mut Output, window: &mut ReorgWindow, latest_block: u64) {
+	let mut number = window.tip().map_or(latest_block, |(tip, _)| tip + 1);
+	while number <= latest_block {
+		let block = match tracker.decode_block(number).await {
+			Ok(block) => block,
+			Err(e) => {
+				eprintln!("Error: {}", e);
+				break;
+			},
+		};
+
+		// A block that doesn't build on the tip means the tip was orphaned, so it's retracted
+		// and its number decoded again from the new branch
+		if !window.extends(block.parent_hash) {
+			match window.retract() {
+				Some(orphan) => {
+					output.grouped_trades(orphan.trades, Some(orphan.number), |trades| TxOutcome::Retracted { trades }).await;
+					number = orphan.number;
+					continue;
+				},
+				None => eprintln!("Error: block {} reorganized blocks that were already final", number),
+			}
+		}
+
+		if window.confirmations() > 1 {
+			output.grouped_trades(block.trades.clone(), Some(block.number), |trades| TxOutcome::Unconfirmed { trades }).await;
+		}
+		for block in window.push(block) {
+			output.save_block(block.number, block.hash, block.timestamp);
+			output.trades(block.trades, Some(block.number)).await;
+		}
+		number += 1;
+	}
+}
+
+/// Follows `newHeads`, reconnecting when the socket drops and catching up on the blocks missed.
+async fn subscribe(tracker: &Tracker<Http>, output: &mut Output, window: &mut ReorgWindow, url: &str) {
+	let mut heads = Box::pin(follow::new_heads(url.to_owned(), follow::RECONNECT_DELAY, follow::IDLE_TIMEOUT));
+	while let Some(head) = heads.next().await {
+		match head {
+			Ok(head) => match head.number {
+				Some(number) => decode_blocks(tracker, output, window, number.as_u64()).await,
+				None => continue,
+			},
+			Err(e) => eprintln!("Error: {}, reconnecting", e),
+		}
+	}
+}
+
+/// Polls for new blocks, retrying a block until the provider has it.
+async fn poll(tracker: &Tracker<Http>, output: &mut Output, window: &mut ReorgWindow, poll_interval: u64) {
+	loop {
+		match tracker.web3.eth().block_number().await {
+			Ok(latest_block) => decode_blocks(tracker, output, window, latest_block.as_u64()).await,
+			Err(e) => eprintln!("Error: {}", e),
+		}
+		tokio::time::sleep(Duration::from_secs(poll_interval)).await;
+	}
+}
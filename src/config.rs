@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::policy::Policy;
+use crate::trade::Chain;
+
+#[derive(Debug)]
+pub enum ConfigError {
+	Io(std::io::Error),
+	Json(serde_json::Error),
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConfigError::Io(e) => write!(f, "failed to read config file: {}", e),
+			ConfigError::Json(e) => write!(f, "malformed config file: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+	fn from(e: std::io::Error) -> Self {
+		ConfigError::Io(e)
+	}
+}
+
+impl From<serde_json::Error> for ConfigError {
+	fn from(e: serde_json::Error) -> Self {
+		ConfigError::Json(e)
+	}
+}
+
+fn default_address_book() -> PathBuf {
+	PathBuf::from("./config/addresses.json")
+}
+
+fn default_token_cache_dir() -> PathBuf {
+	PathBuf::from("./cache/tokens")
+}
+
+fn default_bind_address() -> SocketAddr {
+	SocketAddr::from(([127, 0, 0, 1], 8080))
+}
+
+fn default_body_limit() -> u64 {
+	1024 * 16
+}
+
+/// Settings of the transaction webhook.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServeConfig {
+	#[serde(default = "default_bind_address")]
+	pub bind_address: SocketAddr,
+	/// Largest request body accepted, in bytes
+	#[serde(default = "default_body_limit")]
+	pub body_limit: u64,
+}
+
+impl Default for ServeConfig {
+	fn default() -> Self {
+		Self {
+			bind_address: default_bind_address(),
+			body_limit: default_body_limit(),
+		}
+	}
+}
+
+/// The tool's config file. RPC providers are set per chain as `<chain>-rpc-provider-url`,
+/// e.g. `eth-rpc-provider-url`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+	#[serde(default = "default_address_book")]
+	pub address_book: PathBuf,
+	#[serde(default = "default_token_cache_dir")]
+	pub token_cache_dir: PathBuf,
+	/// Policies by chain id
+	#[serde(default)]
+	pub policy: HashMap<String, Policy>,
+	/// SQLite database decoded trades are stored in, nothing is stored when unset
+	pub store: Option<PathBuf>,
+	/// Blocks on top of which followed blocks are final by chain id, 1 when unset
+	#[serde(default)]
+	pub confirmations: HashMap<String, u64>,
+	/// Block to index Uniswap V1 exchanges from at startup, no indexing when unset
+	pub uniswap_v1_index_from_block: Option<u64>,
+	#[serde(default)]
+	pub serve: ServeConfig,
+	#[serde(flatten)]
+	other: HashMap<String, Value>,
+}
+
+impl Config {
+	pub fn load(path: &Path) -> Result<Self, ConfigError> {
+		Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	/// The chain's RPC provider, `None` if it isn't set or is left empty.
+	pub fn rpc_provider_url(&self, chain: Chain) -> Option<&str> {
+		self.other.get(&format!("{}-rpc-provider-url", chain))
+			.and_then(Value::as_str)
+			.filter(|url| !url.is_empty())
+	}
+
+	/// The chain's WebSocket provider for subscriptions, `None` if it isn't set or is left empty.
+	pub fn ws_provider_url(&self, chain: Chain) -> Option<&str> {
+		self.other.get(&format!("{}-ws-provider-url", chain))
+			.and_then(Value::as_str)
+			.filter(|url| !url.is_empty())
+	}
+
+	/// How many blocks, counting its own, make a followed block final on the chain.
+	pub fn confirmations(&self, chain: Chain) -> u64 {
+		self.confirmations.get(&chain.id().to_string()).copied().unwrap_or(1).max(1)
+	}
+
+	/// The chain's policy, which allows everything if the config doesn't set one.
+	pub fn policy(&self, chain: Chain) -> Policy {
+		self.policy.get(&chain.id().to_string()).cloned().unwrap_or_default()
+	}
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use web3::ethabi::{self, Function, Token};
+use web3::types::{Address, Transaction, TransactionReceipt, U256};
+
+use crate::addresses::AddressBook;
+use crate::logs;
+use crate::trade::{Chain, Dex, Trade};
+
+pub mod pancakeswap;
+pub mod uniswap_v1;
+pub mod uniswap_v2;
+pub mod universal_router;
+
+pub use pancakeswap::PancakeSwapDecoder;
+pub use uniswap_v1::UniswapV1Decoder;
+pub use uniswap_v2::UniswapV2Decoder;
+pub use universal_router::UniversalRouterDecoder;
+
+/// Recipient placeholders the Universal Router and PancakeSwap Smart Router replace with
+/// `msg.sender` and their own address.
+const MSG_SENDER: u64 = 1;
+const ADDRESS_THIS: u64 = 2;
+
+#[derive(Debug)]
+pub enum DecodeError {
+	Abi(ethabi::Error),
+	UnknownFunction(Vec<u8>),
+	UnsupportedFunction(String),
+	MissingParam(String),
+	MalformedPath(Vec<u8>),
+	/// A Uniswap V1 exchange whose token isn't known
+	UnknownExchange(Address),
+	NoDecoder(Option<Address>),
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DecodeError::Abi(e) => write!(f, "failed to decode input data: {}", e),
+			DecodeError::UnknownFunction(selector) => write!(f, "failed to find called function 0x{} in contract ABI", hex::encode(selector)),
+			DecodeError::UnsupportedFunction(name) => write!(f, "function {} is not supported", name),
+			DecodeError::MissingParam(name) => write!(f, "missing or malformed param {}", name),
+			DecodeError::MalformedPath(path) => write!(f, "malformed swap path 0x{}", hex::encode(path)),
+			DecodeError::UnknownExchange(address) => write!(f, "unknown exchange {:?}", address),
+			DecodeError::NoDecoder(Some(address)) => write!(f, "unknown contract {:?}", address),
+			DecodeError::NoDecoder(None) => write!(f, "tx is a contract creation"),
+		}
+	}
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<ethabi::Error> for DecodeError {
+	fn from(e: ethabi::Error) -> Self {
+		DecodeError::Abi(e)
+	}
+}
+
+/// Turns transactions sent to one protocol's contracts into trades.
+pub trait DexDecoder: Send + Sync {
+	fn name(&self) -> &str;
+
+	/// Whether a transaction sent to `to` on `chain` is meant for this decoder.
+	fn matches(&self, to: Address, chain: Chain) -> bool;
+
+	/// Decodes the trades a transaction's input asks for, without the receipt, so pending
+	/// transactions can be decoded too.
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError>;
+
+	/// The wrapped native coin the protocol swaps the native coin through, if it does.
+	fn wrapped_native(&self) -> Option<Address> {
+		None
+	}
+
+	/// Decodes a mined transaction, with the amounts its swap events show were executed.
+	fn decode(&self, tx: &Transaction, receipt: &TransactionReceipt) -> Result<Vec<Trade>, DecodeError> {
+		let mut trades = self.decode_input(tx)?;
+		logs::fill_executed(&mut trades, receipt, self.wrapped_native());
+		Ok(trades)
+	}
+}
+
+impl<D: DexDecoder + ?Sized> DexDecoder for Arc<D> {
+	fn name(&self) -> &str {
+		self.as_ref().name()
+	}
+
+	fn matches(&self, to: Address, chain: Chain) -> bool {
+		self.as_ref().matches(to, chain)
+	}
+
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError> {
+		self.as_ref().decode_input(tx)
+	}
+
+	fn wrapped_native(&self) -> Option<Address> {
+		self.as_ref().wrapped_native()
+	}
+
+	fn decode(&self, tx: &Transaction, receipt: &TransactionReceipt) -> Result<Vec<Trade>, DecodeError> {
+		self.as_ref().decode(tx, receipt)
+	}
+}
+
+/// Picks the decoder for a transaction by the router or exchange it was sent to.
+#[derive(Default)]
+pub struct Registry {
+	decoders: Vec<Box<dyn DexDecoder>>,
+	/// Kept apart so exchanges discovered through the factory can be added to them
+	uniswap_v1: Vec<Arc<UniswapV1Decoder>>,
+}
+
+impl Registry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// A registry with decoders for every router and exchange in the address book, by the
+	/// name they're listed under. Routers without a decoder are skipped.
+	pub fn from_address_book(book: &AddressBook) -> Result<Self, DecodeError> {
+		let mut registry = Self::new();
+
+		for (chain_id, addresses) in &book.chains {
+			let chain = match Chain::from_id(*chain_id) {
+				Some(chain) => chain,
+				None => continue,
+			};
+			let weth = addresses.wrapped_native();
+
+			// Exchanges missing from the address book can still be discovered through the factory
+			if addresses.pools.contains_key("uniswap-v1") || addresses.factories.contains_key("uniswap-v1") {
+				let exchanges: HashMap<Address, Address> = addresses.pools.get("uniswap-v1").into_iter()
+					.flatten()
+					.filter_map(|(symbol, exchange)| addresses.token(symbol).map(|token| (*exchange, token)))
+					.collect();
+				let decoder = Arc::new(UniswapV1Decoder::new(chain, exchanges)?);
+				registry.uniswap_v1.push(decoder.clone());
+				registry.register(decoder);
+			}
+			for (name, router) in &addresses.routers {
+				match name.as_str() {
+					"uniswap-v2-router02" => registry.register(UniswapV2Decoder::new(chain, Dex::Uniswap, *router, weth)?),
+					"pancakeswap-v2-router" => registry.register(UniswapV2Decoder::new(chain, Dex::PancakeSwap, *router, weth)?),
+					"uniswap-universal-router" => registry.register(UniversalRouterDecoder::new(chain, *router, weth)?),
+					"pancakeswap-smart-router" => registry.register(PancakeSwapDecoder::new(chain, *router, weth)?),
+					_ => {},
+				}
+			}
+		}
+
+		Ok(registry)
+	}
+
+	pub fn register(&mut self, decoder: impl DexDecoder + 'static) {
+		self.decoders.push(Box::new(decoder));
+	}
+
+	/// The Uniswap V1 decoder of `chain`, to add exchanges to.
+	pub fn uniswap_v1(&self, chain: Chain) -> Option<&UniswapV1Decoder> {
+		self.uniswap_v1.iter()
+			.find(|decoder| decoder.chain() == chain)
+			.map(|decoder| decoder.as_ref())
+	}
+
+	pub fn find(&self, to: Address, chain: Chain) -> Option<&dyn DexDecoder> {
+		self.decoders.iter()
+			.find(|decoder| decoder.matches(to, chain))
+			.map(|decoder| decoder.as_ref())
+	}
+
+	/// Decodes `tx` with the decoder registered for the contract it was sent to.
+	pub fn decode(&self, chain: Chain, tx: &Transaction, receipt: &TransactionReceipt) -> Result<Vec<Trade>, DecodeError> {
+		let to = tx.to.ok_or(DecodeError::NoDecoder(None))?;
+		match self.find(to, chain) {
+			Some(decoder) => decoder.decode(tx, receipt),
+			None => Err(DecodeError::NoDecoder(Some(to))),
+		}
+	}
+}
+
+/// The address a router's `recipient` argument stands for.
+pub(crate) fn resolve_recipient(tx: &Transaction, router: Address, recipient: Address) -> Address {
+	if recipient == Address::from_low_u64_be(MSG_SENDER) {
+		tx.from.unwrap_or_default()
+	} else if recipient == Address::from_low_u64_be(ADDRESS_THIS) {
+		router
+	} else {
+		recipient
+	}
+}
+
+pub(crate) fn unknown_function(input: &[u8]) -> DecodeError {
+	DecodeError::UnknownFunction(input.iter().take(4).copied().collect())
+}
+
+/// Pairs each decoded param with its name in the ABI.
+pub(crate) fn named_params(function: &Function, values: Vec<Token>) -> HashMap<String, Token> {
+	function.inputs.iter()
+		.map(|param| param.name.clone())
+		.zip(values)
+		.collect()
+}
+
+pub(crate) fn uint_param(params: &HashMap<String, Token>, name: &str) -> Result<U256, DecodeError> {
+	params.get(name)
+		.and_then(|value| value.clone().into_uint())
+		.ok_or_else(|| DecodeError::MissingParam(name.to_owned()))
+}
+
+pub(crate) fn address_param(params: &HashMap<String, Token>, name: &str) -> Result<Address, DecodeError> {
+	params.get(name)
+		.and_then(|value| value.clone().into_address())
+		.ok_or_else(|| DecodeError::MissingParam(name.to_owned()))
+}
+
+pub(crate) fn address_array_param(params: &HashMap<String, Token>, name: &str) -> Result<Vec<Address>, DecodeError> {
+	params.get(name)
+		.and_then(|value| value.clone().into_array())
+		.map(|values| values.into_iter().filter_map(|value| value.into_address()).collect::<Vec<Address>>())
+		.filter(|addresses| !addresses.is_empty())
+		.ok_or_else(|| DecodeError::MissingParam(name.to_owned()))
+}
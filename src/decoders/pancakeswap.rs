@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use web3::ethabi::{Contract, Function, Token};
+use web3::types::{Address, Transaction, U256};
+
+use crate::abi;
+use crate::decoders::{address_array_param, address_param, named_params, resolve_recipient, uint_param, unknown_function, DecodeError, DexDecoder};
+use crate::path::{Hop, Path};
+use crate::trade::{Asset, Chain, Dex, Protocol, Trade, TradeKind};
+
+pub struct PancakeSwapDecoder {
+	chain: Chain,
+	address: Address,
+	/// Wrapped native coin (WETH, WBNB) the router wraps and unwraps
+	weth: Address,
+	contract: Contract,
+}
+
+impl PancakeSwapDecoder {
+	pub fn new(chain: Chain, address: Address, weth: Address) -> Result<Self, DecodeError> {
+		Ok(Self {
+			chain,
+			address,
+			weth,
+			contract: abi::load(abi::PANCAKESWAP_SMART_ROUTER)?,
+		})
+	}
+
+	fn decode_call(&self, input: &[u8]) -> Result<(&Function, HashMap<String, Token>), DecodeError> {
+		let function = abi::called_function(&self.contract, input)
+			.ok_or_else(|| unknown_function(input))?;
+		let params = named_params(function, function.decode_input(&input[4..])?);
+		Ok((function, params))
+	}
+
+	/// Recipient of a tail call, which defaults to the caller for the overloads without one.
+	fn tail_recipient(&self, tx: &Transaction, params: &HashMap<String, Token>) -> Result<Address, DecodeError> {
+		if params.contains_key("recipient") {
+			Ok(resolve_recipient(tx, self.address, address_param(params, "recipient")?))
+		} else {
+			Ok(tx.from.unwrap_or_default())
+		}
+	}
+
+	fn trade(&self, tx: &Transaction, function_name: &str, params: &HashMap<String, Token>, deadline: Option<U256>) -> Result<Option<Trade>, DecodeError> {
+		// The V3 swaps take a single struct, which is decoded as a tuple without its field names
+		let struct_params = || match params.get("params") {
+			Some(Token::Tuple(values)) => Ok(values.clone()),
+			_ => Err(DecodeError::MissingParam("params".to_owned())),
+		};
+		let uint = |values: &[Token], index: usize| values.get(index)
+			.and_then(|value| value.clone().into_uint())
+			.ok_or_else(|| DecodeError::MissingParam(format!("params.{}", index)));
+		let address = |values: &[Token], index: usize| values.get(index)
+			.and_then(|value| value.clone().into_address())
+			.ok_or_else(|| DecodeError::MissingParam(format!("params.{}", index)));
+
+		let mut route: Option<Path> = None;
+		let (protocol, kind, path, recipient, amount, bound) = match function_name {
+			// params: (tokenIn, tokenOut, fee, recipient, amountIn / amountOut, amountOutMinimum / amountInMaximum, sqrtPriceLimitX96)
+			"exactInputSingle" | "exactOutputSingle" => {
+				let values = struct_params()?;
+				let kind = if function_name == "exactInputSingle" { TradeKind::ExactIn } else { TradeKind::ExactOut };
+				let (token_in, token_out) = (address(&values, 0)?, address(&values, 1)?);
+				let fee = uint(&values, 2)?.low_u32();
+				route = Some(Path { hops: vec![Hop { token_in, token_out, fee }] });
+				(Protocol::V3, kind, vec![token_in, token_out], address(&values, 3)?, uint(&values, 4)?, uint(&values, 5)?)
+			},
+			// params: (path, recipient, amountIn / amountOut, amountOutMinimum / amountInMaximum)
+			"exactInput" | "exactOutput" => {
+				let values = struct_params()?;
+				let kind = if function_name == "exactInput" { TradeKind::ExactIn } else { TradeKind::ExactOut };
+				let packed_path = values.first()
+					.and_then(|path| path.clone().into_bytes())
+					.ok_or_else(|| DecodeError::MissingParam("params.0".to_owned()))?;
+				let decoded = Path::decode(&packed_path, kind)?;
+				let path = decoded.tokens();
+				route = Some(decoded);
+				(Protocol::V3, kind, path, address(&values, 1)?, uint(&values, 2)?, uint(&values, 3)?)
+			},
+			"exactInputStableSwap" => {
+				let path = address_array_param(params, "path")?;
+				(Protocol::StableSwap, TradeKind::ExactIn, path, address_param(params, "to")?, uint_param(params, "amountIn")?, uint_param(params, "amountOutMin")?)
+			},
+			"exactOutputStableSwap" => {
+				let path = address_array_param(params, "path")?;
+				(Protocol::StableSwap, TradeKind::ExactOut, path, address_param(params, "to")?, uint_param(params, "amountOut")?, uint_param(params, "amountInMax")?)
+			},
+			"swapExactTokensForTokens" => {
+				let path = address_array_param(params, "path")?;
+				(Protocol::V2, TradeKind::ExactIn, path, address_param(params, "to")?, uint_param(params, "amountIn")?, uint_param(params, "amountOutMin")?)
+			},
+			"swapTokensForExactTokens" => {
+				let path = address_array_param(params, "path")?;
+				(Protocol::V2, TradeKind::ExactOut, path, address_param(params, "to")?, uint_param(params, "amountOut")?, uint_param(params, "amountInMax")?)
+			},
+			_ => return Ok(None),
+		};
+		let (amount_in, amount_out) = kind.amounts(amount);
+		// An amount in of zero swaps the router's balance, left by an earlier call of the multicall
+		let amount_in = amount_in.filter(|amount| !amount.is_zero());
+
+		Ok(Some(Trade {
+			chain: self.chain,
+			dex: Dex::PancakeSwap,
+			protocol,
+			tx_hash: tx.hash,
+			trader: tx.from.unwrap_or_default(),
+			recipient: resolve_recipient(tx, self.address, recipient),
+			token_in: Asset::Token(path[0]),
+			token_out: Asset::Token(path[path.len() - 1]),
+			amount_in,
+			amount_out,
+			kind,
+			bound,
+			path,
+			route,
+			deadline,
+			executed: None,
+			verdict: None,
+		}))
+	}
+}
+
+impl DexDecoder for PancakeSwapDecoder {
+	fn name(&self) -> &str {
+		"pancakeswap smart router"
+	}
+
+	fn matches(&self, to: Address, chain: Chain) -> bool {
+		chain == self.chain && to == self.address
+	}
+
+	fn wrapped_native(&self) -> Option<Address> {
+		Some(self.weth)
+	}
+
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError> {
+		let (function, params) = self.decode_call(&tx.input.0)?;
+
+		// Swaps are usually batched in a multicall, but can also be called on the router directly
+		let mut calls: Vec<(String, HashMap<String, Token>)> = Vec::new();
+		let mut deadline: Option<U256> = None;
+		if function.name == "multicall" {
+			deadline = params.get("deadline").and_then(|deadline| deadline.clone().into_uint());
+			let data = params.get("data")
+				.and_then(|data| data.clone().into_array())
+				.ok_or_else(|| DecodeError::MissingParam("data".to_owned()))?;
+			for call in data {
+				let bytes = call.into_bytes().ok_or_else(|| DecodeError::MissingParam("data".to_owned()))?;
+				let (inner, inner_params) = self.decode_call(&bytes)?;
+				calls.push((inner.name.clone(), inner_params));
+			}
+		} else {
+			calls.push((function.name.clone(), params));
+		}
+
+		let mut trades: Vec<Trade> = Vec::new();
+		for (name, params) in &calls {
+			trades.extend(self.trade(tx, name, params, deadline)?);
+		}
+
+		// Native coins sent with the call are wrapped by the router to pay for swaps from the wrapped
+		// token, and whatever isn't spent is handed back by a trailing refundETH
+		if !tx.value.is_zero() {
+			let refunds = calls.iter().any(|(name, _)| name == "refundETH");
+			for trade in trades.iter_mut().filter(|trade| trade.token_in == Asset::Token(self.weth)) {
+				trade.token_in = Asset::Native;
+				if trade.kind == TradeKind::ExactOut && !refunds {
+					// Without a refund everything sent is spent
+					trade.bound = tx.value;
+				}
+			}
+		}
+
+		// Swaps that leave their output in the router are paid out by unwrapWETH9 or sweepToken
+		for (name, params) in &calls {
+			let (token, unwraps) = match name.as_str() {
+				"unwrapWETH9" | "unwrapWETH9WithFee" => (self.weth, true),
+				"sweepToken" | "sweepTokenWithFee" => (address_param(params, "token")?, false),
+				_ => continue,
+			};
+			let recipient = self.tail_recipient(tx, params)?;
+			let minimum = uint_param(params, "amountMinimum")?;
+			for trade in trades.iter_mut().filter(|trade| trade.recipient == self.address && trade.token_out == Asset::Token(token)) {
+				if unwraps {
+					trade.token_out = Asset::Native;
+				}
+				trade.recipient = recipient;
+				if trade.kind == TradeKind::ExactIn && minimum > trade.bound {
+					trade.bound = minimum;
+				}
+			}
+		}
+
+		Ok(trades)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::{Bytes, H256};
+
+	use super::*;
+	use crate::decoders::{ADDRESS_THIS, MSG_SENDER};
+
+	// BSC contracts, so the calldata reads as it does on chain
+	const ROUTER: &str = "0x13f4EA83D0bd40E75C8222255bc855a974568Dd4";
+	const WBNB: &str = "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c";
+	const USDT: &str = "0x55d398326f99059fF775485246999027B3197955";
+	const CAKE: &str = "0x0E09FaBB73Bd3Ade0a17ECC321fD13a19e81cE82";
+	const TRADER: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+	fn address(address: &str) -> Token {
+		Token::Address(address.parse().unwrap())
+	}
+
+	fn amount(amount: u128) -> Token {
+		Token::Uint(U256::from(amount))
+	}
+
+	#[test]
+	fn leaves_router_balance_amounts_to_the_receipt() {
+		let decoder = PancakeSwapDecoder::new(Chain::Bsc, ROUTER.parse().unwrap(), WBNB.parse().unwrap()).unwrap();
+		let call = |name: &str, params: &[Token]| {
+			let function = decoder.contract.function(name).unwrap();
+			Token::Bytes(function.encode_input(params).unwrap())
+		};
+		// 100 USDT for WBNB left in the router, then all of that WBNB for CAKE
+		let calls = vec![
+			call("swapExactTokensForTokens", &[amount(100_000_000_000_000_000_000), amount(0), Token::Array(vec![address(USDT), address(WBNB)]), Token::Address(Address::from_low_u64_be(ADDRESS_THIS))]),
+			call("exactInputSingle", &[Token::Tuple(vec![address(WBNB), address(CAKE), amount(2500), Token::Address(Address::from_low_u64_be(MSG_SENDER)), amount(0), amount(40_000_000_000_000_000_000), amount(0)])]),
+		];
+		let multicall = decoder.contract.functions_by_name("multicall").unwrap().iter()
+			.find(|function| function.inputs.first().is_some_and(|input| input.name == "deadline"))
+			.unwrap();
+		let tx = Transaction {
+			hash: H256::repeat_byte(0xab),
+			from: TRADER.parse().ok(),
+			to: ROUTER.parse().ok(),
+			input: Bytes(multicall.encode_input(&[amount(1_700_000_000), Token::Array(calls)]).unwrap()),
+			..Default::default()
+		};
+		let trades = decoder.decode_input(&tx).unwrap();
+
+		assert_eq!(trades.len(), 2);
+		assert_eq!(trades[0].amount_in, Some(U256::exp10(20)));
+		assert_eq!(trades[1].amount_in, None);
+		assert_eq!(trades[1].token_in, Asset::Token(WBNB.parse().unwrap()));
+		assert_eq!(trades[1].recipient, TRADER.parse().unwrap());
+		assert_eq!(trades[1].bound, U256::from(40_000_000_000_000_000_000u128));
+	}
+}
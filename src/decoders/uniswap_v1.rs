@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use web3::ethabi::Contract;
+use web3::types::{Address, Transaction, U256};
+
+use crate::abi;
+use crate::decoders::{address_param, named_params, uint_param, unknown_function, DecodeError, DexDecoder};
+use crate::trade::{Asset, Chain, Dex, Protocol, Trade, TradeKind};
+
+pub struct UniswapV1Decoder {
+	chain: Chain,
+	contract: Contract,
+	/// Exchange address to the address of the token it trades, grows as exchanges are discovered
+	exchanges: RwLock<HashMap<Address, Address>>,
+}
+
+impl UniswapV1Decoder {
+	pub fn new(chain: Chain, exchanges: HashMap<Address, Address>) -> Result<Self, DecodeError> {
+		Ok(Self {
+			chain,
+			contract: abi::load(abi::UNISWAP_V1_EXCHANGE)?,
+			exchanges: RwLock::new(exchanges),
+		})
+	}
+
+	pub fn chain(&self) -> Chain {
+		self.chain
+	}
+
+	pub fn token(&self, exchange: Address) -> Option<Address> {
+		self.exchanges.read().unwrap().get(&exchange).copied()
+	}
+
+	pub fn add_exchange(&self, exchange: Address, token: Address) {
+		self.exchanges.write().unwrap().insert(exchange, token);
+	}
+
+	/// Exchanges `tx` trades through if it calls a V1 exchange swap: the called exchange, and
+	/// the exchange of the output token for token to exchange swaps. Empty for anything else,
+	/// so other contracts aren't mistaken for exchanges to look up.
+	pub fn exchanges_called(&self, tx: &Transaction) -> Vec<Address> {
+		let (to, input) = match tx.to {
+			Some(to) => (to, tx.input.0.as_slice()),
+			None => return Vec::new(),
+		};
+		// Plain ETH transfers to an exchange buy its token
+		if input.is_empty() {
+			return if tx.value.is_zero() { Vec::new() } else { vec![to] };
+		}
+		let function = match abi::called_function(&self.contract, input) {
+			Some(function) if is_swap(&function.name) => function,
+			_ => return Vec::new(),
+		};
+
+		let mut exchanges = vec![to];
+		if let Ok(values) = function.decode_input(&input[4..]) {
+			exchanges.extend(address_param(&named_params(function, values), "exchange_addr").ok());
+		}
+		exchanges
+	}
+}
+
+/// Whether an exchange function swaps, rather than adding liquidity or moving the exchange's
+/// own liquidity token, whose ERC-20 functions any token shares.
+fn is_swap(name: &str) -> bool {
+	["ethToToken", "tokenToEth", "tokenToToken", "tokenToExchange"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+impl DexDecoder for UniswapV1Decoder {
+	fn name(&self) -> &str {
+		"uniswap v1"
+	}
+
+	fn matches(&self, to: Address, chain: Chain) -> bool {
+		chain == self.chain && self.token(to).is_some()
+	}
+
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError> {
+		let input_data = tx.input.0.as_slice();
+
+		// Plain ETH transfers to an exchange land in its default function
+		let (function_name, params) = if input_data.is_empty() {
+			("__default__", HashMap::new())
+		} else {
+			let function = abi::called_function(&self.contract, input_data)
+				.ok_or_else(|| unknown_function(input_data))?;
+			let values = function.decode_input(&input_data[4..])?;
+			(function.name.as_str(), named_params(function, values))
+		};
+
+		// The token traded by the called exchange
+		let exchange_token = match tx.to.and_then(|exchange| self.token(exchange)) {
+			Some(token) => Asset::Token(token),
+			None => return Ok(Vec::new()),
+		};
+
+		// Token to token trades route through the exchange of the output token
+		let token_out = || -> Result<Asset, DecodeError> {
+			if params.contains_key("token_addr") {
+				Ok(Asset::Token(address_param(&params, "token_addr")?))
+			} else {
+				let exchange = address_param(&params, "exchange_addr")?;
+				match self.token(exchange) {
+					Some(token) => Ok(Asset::Token(token)),
+					None => Err(DecodeError::UnknownExchange(exchange)),
+				}
+			}
+		};
+
+		let (kind, amount, bound, token_in, token_out) = match function_name {
+			"__default__" => (TradeKind::ExactIn, tx.value, U256::zero(), Asset::Native, exchange_token),
+			"ethToTokenSwapInput" | "ethToTokenTransferInput" => {
+				(TradeKind::ExactIn, tx.value, uint_param(&params, "min_tokens")?, Asset::Native, exchange_token)
+			},
+			"ethToTokenSwapOutput" | "ethToTokenTransferOutput" => {
+				(TradeKind::ExactOut, uint_param(&params, "tokens_bought")?, tx.value, Asset::Native, exchange_token)
+			},
+			"tokenToEthSwapInput" | "tokenToEthTransferInput" => {
+				(TradeKind::ExactIn, uint_param(&params, "tokens_sold")?, uint_param(&params, "min_eth")?, exchange_token, Asset::Native)
+			},
+			"tokenToEthSwapOutput" | "tokenToEthTransferOutput" => {
+				(TradeKind::ExactOut, uint_param(&params, "eth_bought")?, uint_param(&params, "max_tokens")?, exchange_token, Asset::Native)
+			},
+			"tokenToTokenSwapInput" | "tokenToTokenTransferInput" | "tokenToExchangeSwapInput" | "tokenToExchangeTransferInput" => {
+				(TradeKind::ExactIn, uint_param(&params, "tokens_sold")?, uint_param(&params, "min_tokens_bought")?, exchange_token, token_out()?)
+			},
+			"tokenToTokenSwapOutput" | "tokenToTokenTransferOutput" | "tokenToExchangeSwapOutput" | "tokenToExchangeTransferOutput" => {
+				(TradeKind::ExactOut, uint_param(&params, "tokens_bought")?, uint_param(&params, "max_tokens_sold")?, exchange_token, token_out()?)
+			},
+			// Called function does not perform a trade
+			_ => return Ok(Vec::new()),
+		};
+
+		let trader = tx.from.unwrap_or_default();
+		let recipient = if params.contains_key("recipient") { address_param(&params, "recipient")? } else { trader };
+		let (amount_in, amount_out) = kind.amounts(amount);
+		let deadline = if params.contains_key("deadline") { Some(uint_param(&params, "deadline")?) } else { None };
+
+		Ok(vec![Trade {
+			chain: self.chain,
+			dex: Dex::Uniswap,
+			protocol: Protocol::V1,
+			tx_hash: tx.hash,
+			trader,
+			recipient,
+			token_in,
+			token_out,
+			amount_in,
+			amount_out,
+			kind,
+			bound,
+			path: Vec::new(),
+			route: None,
+			deadline,
+			executed: None,
+			verdict: None,
+		}])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::ethabi::Token;
+	use web3::types::Bytes;
+
+	use super::*;
+
+	// The USDC exchange, and the USDT exchange token to exchange swaps may pay out through
+	const USDC_EXCHANGE: &str = "0x97deC872013f6B5fB443861090ad931542878126";
+	const USDT_EXCHANGE: &str = "0xc8313c965C47D1E0B5cDCD757B210356AD0e400C";
+
+	fn exchange() -> Address {
+		USDC_EXCHANGE.parse().unwrap()
+	}
+
+	fn call(decoder: &UniswapV1Decoder, name: &str, params: &[Token], value: u64) -> Transaction {
+		let input = decoder.contract.function(name).unwrap().encode_input(params).unwrap();
+		Transaction { to: Some(exchange()), value: U256::from(value), input: Bytes(input), ..Default::default() }
+	}
+
+	#[test]
+	fn only_swaps_call_exchanges() {
+		let decoder = UniswapV1Decoder::new(Chain::Ethereum, HashMap::new()).unwrap();
+		let uint = |value: u64| Token::Uint(U256::from(value));
+
+		let swap = call(&decoder, "ethToTokenSwapInput", &[uint(1), uint(1_700_000_000)], 1_000);
+		assert_eq!(decoder.exchanges_called(&swap), vec![exchange()]);
+		let to_exchange = call(&decoder, "tokenToExchangeSwapInput", &[uint(1), uint(1), uint(1), uint(1_700_000_000), Token::Address(USDT_EXCHANGE.parse().unwrap())], 0);
+		assert_eq!(decoder.exchanges_called(&to_exchange), vec![exchange(), USDT_EXCHANGE.parse().unwrap()]);
+
+		// Every token shares the ERC-20 functions of the exchanges' liquidity tokens
+		let transfer = call(&decoder, "transfer", &[Token::Address(Address::from_low_u64_be(7)), uint(1)], 0);
+		assert!(decoder.exchanges_called(&transfer).is_empty());
+
+		let plain = Transaction { to: Some(exchange()), value: U256::from(1_000), ..Default::default() };
+		assert_eq!(decoder.exchanges_called(&plain), vec![exchange()]);
+		let empty = Transaction { to: Some(exchange()), ..Default::default() };
+		assert!(decoder.exchanges_called(&empty).is_empty());
+	}
+}
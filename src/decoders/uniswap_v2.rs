@@ -0,0 +1,91 @@
+use web3::ethabi::Contract;
+use web3::types::{Address, Transaction};
+
+use crate::abi;
+use crate::decoders::{address_array_param, address_param, named_params, uint_param, unknown_function, DecodeError, DexDecoder};
+use crate::trade::{Asset, Chain, Dex, Protocol, Trade, TradeKind};
+
+/// Decodes swaps through Uniswap V2 Router02, or any router sharing its ABI (e.g. PancakeSwap V2).
+pub struct UniswapV2Decoder {
+	chain: Chain,
+	dex: Dex,
+	address: Address,
+	/// Wrapped native coin (WETH, WBNB) the ETH swaps route through
+	weth: Address,
+	contract: Contract,
+}
+
+impl UniswapV2Decoder {
+	pub fn new(chain: Chain, dex: Dex, address: Address, weth: Address) -> Result<Self, DecodeError> {
+		Ok(Self {
+			chain,
+			dex,
+			address,
+			weth,
+			contract: abi::load(abi::UNISWAP_V2_ROUTER02)?,
+		})
+	}
+}
+
+impl DexDecoder for UniswapV2Decoder {
+	fn name(&self) -> &str {
+		match self.dex {
+			Dex::PancakeSwap => "pancakeswap v2 router",
+			_ => "uniswap v2 router02",
+		}
+	}
+
+	fn matches(&self, to: Address, chain: Chain) -> bool {
+		chain == self.chain && to == self.address
+	}
+
+	fn wrapped_native(&self) -> Option<Address> {
+		Some(self.weth)
+	}
+
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError> {
+		let input_data = tx.input.0.as_slice();
+		let function = abi::called_function(&self.contract, input_data)
+			.ok_or_else(|| unknown_function(input_data))?;
+		let params = named_params(function, function.decode_input(&input_data[4..])?);
+
+		// The SupportingFeeOnTransferTokens variants take the same params as the plain swaps,
+		// and ETH legs are swapped through the wrapped native coin at the ends of the path
+		let (kind, amount, bound, eth_in, eth_out) = match function.name.trim_end_matches("SupportingFeeOnTransferTokens") {
+			"swapExactTokensForTokens" => (TradeKind::ExactIn, uint_param(&params, "amountIn")?, uint_param(&params, "amountOutMin")?, false, false),
+			"swapExactTokensForETH" => (TradeKind::ExactIn, uint_param(&params, "amountIn")?, uint_param(&params, "amountOutMin")?, false, true),
+			"swapExactETHForTokens" => (TradeKind::ExactIn, tx.value, uint_param(&params, "amountOutMin")?, true, false),
+			"swapTokensForExactTokens" => (TradeKind::ExactOut, uint_param(&params, "amountOut")?, uint_param(&params, "amountInMax")?, false, false),
+			"swapTokensForExactETH" => (TradeKind::ExactOut, uint_param(&params, "amountOut")?, uint_param(&params, "amountInMax")?, false, true),
+			// Any ETH sent over the amount needed is refunded
+			"swapETHForExactTokens" => (TradeKind::ExactOut, uint_param(&params, "amountOut")?, tx.value, true, false),
+			// Called function does not perform a trade
+			_ => return Ok(Vec::new()),
+		};
+
+		let path = address_array_param(&params, "path")?;
+		let token_in = if eth_in { Asset::Native } else { Asset::Token(path[0]) };
+		let token_out = if eth_out { Asset::Native } else { Asset::Token(path[path.len() - 1]) };
+		let (amount_in, amount_out) = kind.amounts(amount);
+
+		Ok(vec![Trade {
+			chain: self.chain,
+			dex: self.dex,
+			protocol: Protocol::V2,
+			tx_hash: tx.hash,
+			trader: tx.from.unwrap_or_default(),
+			recipient: address_param(&params, "to")?,
+			token_in,
+			token_out,
+			amount_in,
+			amount_out,
+			kind,
+			bound,
+			path,
+			route: None,
+			deadline: Some(uint_param(&params, "deadline")?),
+			executed: None,
+			verdict: None,
+		}])
+	}
+}
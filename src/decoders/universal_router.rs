@@ -0,0 +1,420 @@
+use web3::ethabi::{self, Contract, ParamType, Token};
+use web3::types::{Address, Transaction, U256};
+
+use crate::abi;
+use crate::decoders::{named_params, resolve_recipient, unknown_function, DecodeError, DexDecoder};
+use crate::path::Path;
+use crate::trade::{Asset, Chain, Dex, Protocol, Trade, TradeKind};
+
+/// Set on a command byte when the router should carry on if that command reverts.
+pub const FLAG_ALLOW_REVERT: u8 = 0x80;
+pub const COMMAND_TYPE_MASK: u8 = 0x3f;
+
+/// Swap amounts standing for the router's whole balance of the token, and for tokens an earlier
+/// step already sent to the pool.
+const CONTRACT_BALANCE: U256 = U256([0, 0, 0, 1 << 63]);
+const ALREADY_PAID: U256 = U256([0, 0, 0, 0]);
+
+/// One command of an `execute` call, with its input decoded by the command's layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+	pub index: usize,
+	/// The command type, without the allow revert flag.
+	pub command: u8,
+	pub name: &'static str,
+	pub allow_revert: bool,
+	pub params: Vec<(&'static str, Token)>,
+}
+
+impl Step {
+	pub fn param(&self, name: &str) -> Option<&Token> {
+		self.params.iter()
+			.find(|(param, _)| *param == name)
+			.map(|(_, value)| value)
+	}
+
+	fn uint(&self, name: &str) -> Result<U256, DecodeError> {
+		self.param(name)
+			.and_then(|value| value.clone().into_uint())
+			.ok_or_else(|| DecodeError::MissingParam(name.to_owned()))
+	}
+
+	fn address(&self, name: &str) -> Result<Address, DecodeError> {
+		self.param(name)
+			.and_then(|value| value.clone().into_address())
+			.ok_or_else(|| DecodeError::MissingParam(name.to_owned()))
+	}
+}
+
+/// Name and input layout of each command, as dispatched by the router as of v1.2.
+fn layout(command: u8) -> Option<(&'static str, Vec<(&'static str, ParamType)>)> {
+	let address = || ParamType::Address;
+	let uint = ParamType::Uint;
+	// (address token, uint160 amount, uint48 expiration, uint48 nonce)
+	let permit_details = || ParamType::Tuple(vec![address(), uint(160), uint(48), uint(48)]);
+
+	let layout = match command {
+		0x00 => ("V3_SWAP_EXACT_IN", vec![("recipient", address()), ("amountIn", uint(256)), ("amountOutMin", uint(256)), ("path", ParamType::Bytes), ("payerIsUser", ParamType::Bool)]),
+		0x01 => ("V3_SWAP_EXACT_OUT", vec![("recipient", address()), ("amountOut", uint(256)), ("amountInMax", uint(256)), ("path", ParamType::Bytes), ("payerIsUser", ParamType::Bool)]),
+		0x02 => ("PERMIT2_TRANSFER_FROM", vec![("token", address()), ("recipient", address()), ("amount", uint(160))]),
+		// (PermitDetails[] details, address spender, uint256 sigDeadline)
+		0x03 => ("PERMIT2_PERMIT_BATCH", vec![("permitBatch", ParamType::Tuple(vec![ParamType::Array(Box::new(permit_details())), address(), uint(256)])), ("signature", ParamType::Bytes)]),
+		0x04 => ("SWEEP", vec![("token", address()), ("recipient", address()), ("amountMin", uint(256))]),
+		0x05 => ("TRANSFER", vec![("token", address()), ("recipient", address()), ("value", uint(256))]),
+		0x06 => ("PAY_PORTION", vec![("token", address()), ("recipient", address()), ("bips", uint(256))]),
+		0x08 => ("V2_SWAP_EXACT_IN", vec![("recipient", address()), ("amountIn", uint(256)), ("amountOutMin", uint(256)), ("path", ParamType::Array(Box::new(address()))), ("payerIsUser", ParamType::Bool)]),
+		0x09 => ("V2_SWAP_EXACT_OUT", vec![("recipient", address()), ("amountOut", uint(256)), ("amountInMax", uint(256)), ("path", ParamType::Array(Box::new(address()))), ("payerIsUser", ParamType::Bool)]),
+		// (PermitDetails details, address spender, uint256 sigDeadline)
+		0x0a => ("PERMIT2_PERMIT", vec![("permitSingle", ParamType::Tuple(vec![permit_details(), address(), uint(256)])), ("signature", ParamType::Bytes)]),
+		0x0b => ("WRAP_ETH", vec![("recipient", address()), ("amountMin", uint(256))]),
+		0x0c => ("UNWRAP_WETH", vec![("recipient", address()), ("amountMin", uint(256))]),
+		// (address from, address to, uint160 amount, address token)[]
+		0x0d => ("PERMIT2_TRANSFER_FROM_BATCH", vec![("batchDetails", ParamType::Array(Box::new(ParamType::Tuple(vec![address(), address(), uint(160), address()]))))]),
+		0x0e => ("BALANCE_CHECK_ERC20", vec![("owner", address()), ("token", address()), ("minBalance", uint(256))]),
+		0x10 => ("SEAPORT_V1_5", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x11 => ("LOOKS_RARE_V2", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x12 => ("NFTX", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x13 => ("CRYPTOPUNKS", vec![("punkId", uint(256)), ("recipient", address()), ("value", uint(256))]),
+		0x15 => ("OWNER_CHECK_721", vec![("owner", address()), ("token", address()), ("id", uint(256))]),
+		0x16 => ("OWNER_CHECK_1155", vec![("owner", address()), ("token", address()), ("id", uint(256)), ("minBalance", uint(256))]),
+		0x17 => ("SWEEP_ERC721", vec![("token", address()), ("recipient", address()), ("id", uint(256))]),
+		0x18 => ("X2Y2_721", vec![("value", uint(256)), ("data", ParamType::Bytes), ("recipient", address()), ("token", address()), ("id", uint(256))]),
+		0x19 => ("SUDOSWAP", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x1a => ("NFT20", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x1b => ("X2Y2_1155", vec![("value", uint(256)), ("data", ParamType::Bytes), ("recipient", address()), ("token", address()), ("id", uint(256)), ("amount", uint(256))]),
+		0x1c => ("FOUNDATION", vec![("value", uint(256)), ("data", ParamType::Bytes), ("recipient", address()), ("token", address()), ("id", uint(256))]),
+		0x1d => ("SWEEP_ERC1155", vec![("token", address()), ("recipient", address()), ("id", uint(256)), ("amount", uint(256))]),
+		0x1e => ("ELEMENT_MARKET", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x20 => ("SEAPORT_V1_4", vec![("value", uint(256)), ("data", ParamType::Bytes)]),
+		0x21 => ("EXECUTE_SUB_PLAN", vec![("commands", ParamType::Bytes), ("inputs", ParamType::Array(Box::new(ParamType::Bytes)))]),
+		// The spender is an enum of the NFT marketplaces the router may approve
+		0x22 => ("APPROVE_ERC20", vec![("token", address()), ("spender", uint(8))]),
+		_ => return None,
+	};
+	Some(layout)
+}
+
+/// Decodes every command of an `execute` call into an ordered list of steps. Commands this
+/// decoder doesn't know are kept as `UNKNOWN` steps with their raw `input`.
+pub fn decode_steps(commands: &[u8], inputs: &[Vec<u8>]) -> Result<Vec<Step>, DecodeError> {
+	if commands.len() != inputs.len() {
+		return Err(DecodeError::MissingParam("inputs".to_owned()));
+	}
+
+	let mut steps: Vec<Step> = Vec::new();
+	for (index, (command, input)) in commands.iter().zip(inputs).enumerate() {
+		let command_type = command & COMMAND_TYPE_MASK;
+		let (name, params) = match layout(command_type) {
+			Some((name, params)) => {
+				let (names, types): (Vec<&'static str>, Vec<ParamType>) = params.into_iter().unzip();
+				(name, names.into_iter().zip(ethabi::decode(&types, input)?).collect())
+			},
+			None => ("UNKNOWN", vec![("input", Token::Bytes(input.clone()))]),
+		};
+		steps.push(Step {
+			index,
+			command: command_type,
+			name,
+			allow_revert: command & FLAG_ALLOW_REVERT != 0,
+			params,
+		});
+	}
+
+	Ok(steps)
+}
+
+pub struct UniversalRouterDecoder {
+	chain: Chain,
+	address: Address,
+	weth: Address,
+	contract: Contract,
+}
+
+impl UniversalRouterDecoder {
+	pub fn new(chain: Chain, address: Address, weth: Address) -> Result<Self, DecodeError> {
+		Ok(Self {
+			chain,
+			address,
+			weth,
+			contract: abi::load(abi::UNISWAP_UNIVERSAL_ROUTER)?,
+		})
+	}
+
+	/// Decodes the steps of a transaction sent to the router.
+	pub fn steps(&self, tx: &Transaction) -> Result<Vec<Step>, DecodeError> {
+		self.execute(tx).map(|(steps, _)| steps)
+	}
+
+	/// Decodes an `execute` call into its steps and deadline.
+	fn execute(&self, tx: &Transaction) -> Result<(Vec<Step>, Option<U256>), DecodeError> {
+		let input_data = tx.input.0.as_slice();
+		let function = abi::called_function(&self.contract, input_data)
+			.ok_or_else(|| unknown_function(input_data))?;
+		let params = named_params(function, function.decode_input(&input_data[4..])?);
+
+		let commands = params.get("commands")
+			.and_then(|commands| commands.clone().into_bytes())
+			.ok_or_else(|| DecodeError::UnsupportedFunction(function.name.clone()))?;
+		let inputs: Vec<Vec<u8>> = params.get("inputs")
+			.and_then(|inputs| inputs.clone().into_array())
+			.ok_or_else(|| DecodeError::MissingParam("inputs".to_owned()))?
+			.into_iter()
+			.filter_map(|input| input.into_bytes())
+			.collect();
+
+		let deadline = params.get("deadline").and_then(|deadline| deadline.clone().into_uint());
+
+		Ok((decode_steps(&commands, &inputs)?, deadline))
+	}
+
+	fn trade(&self, tx: &Transaction, step: &Step, deadline: Option<U256>) -> Result<Option<Trade>, DecodeError> {
+		let (protocol, kind) = match step.name {
+			"V3_SWAP_EXACT_IN" => (Protocol::V3, TradeKind::ExactIn),
+			"V3_SWAP_EXACT_OUT" => (Protocol::V3, TradeKind::ExactOut),
+			"V2_SWAP_EXACT_IN" => (Protocol::V2, TradeKind::ExactIn),
+			"V2_SWAP_EXACT_OUT" => (Protocol::V2, TradeKind::ExactOut),
+			_ => return Ok(None),
+		};
+		let (amount, bound) = match kind {
+			TradeKind::ExactIn => (step.uint("amountIn")?, step.uint("amountOutMin")?),
+			TradeKind::ExactOut => (step.uint("amountOut")?, step.uint("amountInMax")?),
+		};
+
+		let (path, route): (Vec<Address>, Option<Path>) = match (protocol, step.param("path")) {
+			(Protocol::V2, Some(Token::Array(path))) => (path.iter().filter_map(|token| token.clone().into_address()).collect(), None),
+			(Protocol::V3, Some(Token::Bytes(path))) => {
+				let route = Path::decode(path, kind)?;
+				(route.tokens(), Some(route))
+			},
+			_ => return Err(DecodeError::MissingParam("path".to_owned())),
+		};
+		let (token_in, token_out) = match (path.first(), path.last()) {
+			(Some(first), Some(last)) => (*first, *last),
+			_ => return Err(DecodeError::MissingParam("path".to_owned())),
+		};
+		let (amount_in, amount_out) = kind.amounts(amount);
+		// Split and multi-hop routes spend what earlier steps left, which only the receipt shows
+		let amount_in = amount_in.filter(|amount| *amount != CONTRACT_BALANCE && *amount != ALREADY_PAID);
+
+		Ok(Some(Trade {
+			chain: self.chain,
+			dex: Dex::Uniswap,
+			protocol,
+			tx_hash: tx.hash,
+			trader: tx.from.unwrap_or_default(),
+			recipient: resolve_recipient(tx, self.address, step.address("recipient")?),
+			token_in: Asset::Token(token_in),
+			token_out: Asset::Token(token_out),
+			amount_in,
+			amount_out,
+			kind,
+			bound,
+			path,
+			route,
+			deadline,
+			executed: None,
+			verdict: None,
+		}))
+	}
+}
+
+impl DexDecoder for UniversalRouterDecoder {
+	fn name(&self) -> &str {
+		"uniswap universal router"
+	}
+
+	fn matches(&self, to: Address, chain: Chain) -> bool {
+		chain == self.chain && to == self.address
+	}
+
+	fn wrapped_native(&self) -> Option<Address> {
+		Some(self.weth)
+	}
+
+	fn decode_input(&self, tx: &Transaction) -> Result<Vec<Trade>, DecodeError> {
+		let (steps, deadline) = self.execute(tx)?;
+
+		let mut trades: Vec<Trade> = Vec::new();
+		for step in &steps {
+			trades.extend(self.trade(tx, step, deadline)?);
+		}
+
+		// ETH is wrapped by the router before it is swapped
+		if steps.iter().any(|step| step.name == "WRAP_ETH") {
+			for trade in trades.iter_mut().filter(|trade| trade.token_in == Asset::Token(self.weth)) {
+				trade.token_in = Asset::Native;
+			}
+		}
+
+		// Swaps that leave their output in the router are paid out by UNWRAP_WETH, SWEEP or TRANSFER.
+		// A TRANSFER of part of the output usually goes along with a SWEEP of the rest to the trader,
+		// so it only stands in for the recipient when nothing unwraps or sweeps the token
+		for name in ["UNWRAP_WETH", "SWEEP", "TRANSFER"] {
+			for step in steps.iter().filter(|step| step.name == name) {
+				let (token, minimum) = match name {
+					"UNWRAP_WETH" => (self.weth, Some(step.uint("amountMin")?)),
+					"SWEEP" => (step.address("token")?, Some(step.uint("amountMin")?)),
+					_ => (step.address("token")?, None),
+				};
+				let recipient = resolve_recipient(tx, self.address, step.address("recipient")?);
+				for trade in trades.iter_mut().filter(|trade| trade.recipient == self.address && trade.token_out == Asset::Token(token)) {
+					if name == "UNWRAP_WETH" {
+						trade.token_out = Asset::Native;
+					}
+					trade.recipient = recipient;
+					match minimum {
+						Some(minimum) if trade.kind == TradeKind::ExactIn && minimum > trade.bound => trade.bound = minimum,
+						_ => {},
+					}
+				}
+			}
+		}
+
+		Ok(trades)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::{Bytes, H256};
+
+	use super::*;
+	use crate::decoders::{ADDRESS_THIS, MSG_SENDER};
+
+	// Mainnet contracts, so the calldata reads as it does on chain
+	const ROUTER: &str = "0xEf1c6E67703c7BD7107eed8303Fbe6EC2554BF6B";
+	const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+	const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+	const TRADER: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+	const FEE_COLLECTOR: &str = "0x000000fee13a103A10D593b9AE06b3e05F2E7E1c";
+
+	fn address(address: &str) -> Address {
+		address.parse().unwrap()
+	}
+
+	fn decoder() -> UniversalRouterDecoder {
+		UniversalRouterDecoder::new(Chain::Ethereum, address(ROUTER), address(WETH)).unwrap()
+	}
+
+	fn placeholder(placeholder: u64) -> Token {
+		Token::Address(Address::from_low_u64_be(placeholder))
+	}
+
+	fn amount(amount: u64) -> Token {
+		Token::Uint(U256::from(amount))
+	}
+
+	/// A packed V3 path through a single pool.
+	fn v3_path(token_in: &str, fee: u32, token_out: &str) -> Token {
+		let mut path = address(token_in).as_bytes().to_vec();
+		path.extend(&fee.to_be_bytes()[1..]);
+		path.extend(address(token_out).as_bytes());
+		Token::Bytes(path)
+	}
+
+	/// A call of `execute(bytes,bytes[],uint256)` sending `value` wei.
+	fn execute(commands: &[u8], inputs: Vec<Vec<Token>>, value: u64) -> Transaction {
+		let contract = abi::load(abi::UNISWAP_UNIVERSAL_ROUTER).unwrap();
+		let function = contract.functions_by_name("execute").unwrap().iter().find(|function| function.inputs.len() == 3).unwrap();
+		let inputs = inputs.iter().map(|input| Token::Bytes(ethabi::encode(input))).collect();
+		let input = function.encode_input(&[Token::Bytes(commands.to_vec()), Token::Array(inputs), amount(1_700_000_000)]).unwrap();
+		Transaction {
+			hash: H256::repeat_byte(0xab),
+			from: Some(address(TRADER)),
+			to: Some(address(ROUTER)),
+			value: U256::from(value),
+			input: Bytes(input),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn decodes_steps_with_the_allow_revert_flag_and_unknown_commands() {
+		let sweep = ethabi::encode(&[Token::Address(address(USDC)), placeholder(MSG_SENDER), amount(0)]);
+		let steps = decode_steps(&[0x04 | FLAG_ALLOW_REVERT, 0x3f], &[sweep, vec![0xde, 0xad]]).unwrap();
+
+		assert_eq!(steps[0].name, "SWEEP");
+		assert_eq!(steps[0].command, 0x04);
+		assert!(steps[0].allow_revert);
+		assert_eq!(steps[0].param("token"), Some(&Token::Address(address(USDC))));
+		assert_eq!(steps[1].name, "UNKNOWN");
+		assert!(!steps[1].allow_revert);
+		assert_eq!(steps[1].param("input"), Some(&Token::Bytes(vec![0xde, 0xad])));
+		assert!(decode_steps(&[0x04], &[]).is_err());
+	}
+
+	#[test]
+	fn swaps_wrapped_eth_as_native() {
+		// 1 ETH for at least 1,800 USDC through the 0.05% pool
+		let tx = execute(&[0x0b, 0x00], vec![
+			vec![placeholder(ADDRESS_THIS), amount(1_000_000_000_000_000_000)],
+			vec![placeholder(MSG_SENDER), amount(1_000_000_000_000_000_000), amount(1_800_000_000), v3_path(WETH, 500, USDC), Token::Bool(false)],
+		], 1_000_000_000_000_000_000);
+		let trades = decoder().decode_input(&tx).unwrap();
+
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].token_in, Asset::Native);
+		assert_eq!(trades[0].token_out, Asset::Token(address(USDC)));
+		assert_eq!(trades[0].recipient, address(TRADER));
+		assert_eq!(trades[0].amount_in, Some(U256::exp10(18)));
+		assert_eq!(trades[0].bound, U256::from(1_800_000_000u64));
+		assert_eq!(trades[0].route.as_ref().map(|route| route.hops[0].fee), Some(500));
+		assert_eq!(trades[0].deadline, Some(U256::from(1_700_000_000u64)));
+	}
+
+	#[test]
+	fn pays_out_unwrapped_eth_to_the_unwrap_recipient() {
+		// 2,000 USDC for WETH left in the router, then unwrapped for the trader
+		let tx = execute(&[0x08, 0x0c], vec![
+			vec![placeholder(ADDRESS_THIS), amount(2_000_000_000), amount(0), Token::Array(vec![Token::Address(address(USDC)), Token::Address(address(WETH))]), Token::Bool(true)],
+			vec![placeholder(MSG_SENDER), amount(990_000_000_000_000_000)],
+		], 0);
+		let trades = decoder().decode_input(&tx).unwrap();
+
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].protocol, Protocol::V2);
+		assert_eq!(trades[0].token_in, Asset::Token(address(USDC)));
+		assert_eq!(trades[0].token_out, Asset::Native);
+		assert_eq!(trades[0].recipient, address(TRADER));
+		assert_eq!(trades[0].bound, U256::from(990_000_000_000_000_000u64));
+	}
+
+	#[test]
+	fn pays_out_swept_and_transferred_tokens_to_their_recipients() {
+		// The output is left in the router, a portion paid as a fee and the rest swept to the trader
+		let sweeps = execute(&[0x00, 0x06, 0x04], vec![
+			vec![placeholder(ADDRESS_THIS), amount(1_000_000_000_000_000_000), amount(1_800_000_000), v3_path(WETH, 3000, USDC), Token::Bool(true)],
+			vec![Token::Address(address(USDC)), Token::Address(address(FEE_COLLECTOR)), amount(15)],
+			vec![Token::Address(address(USDC)), placeholder(MSG_SENDER), amount(1_797_300_000)],
+		], 0);
+		let trades = decoder().decode_input(&sweeps).unwrap();
+		assert_eq!(trades[0].recipient, address(TRADER));
+		assert_eq!(trades[0].bound, U256::from(1_800_000_000u64));
+
+		// Without a sweep, a transfer of the output pays it out
+		let transfers = execute(&[0x00, 0x05], vec![
+			vec![placeholder(ADDRESS_THIS), amount(1_000_000_000_000_000_000), amount(1_800_000_000), v3_path(WETH, 3000, USDC), Token::Bool(true)],
+			vec![Token::Address(address(USDC)), Token::Address(address(FEE_COLLECTOR)), amount(1_800_000_000)],
+		], 0);
+		let trades = decoder().decode_input(&transfers).unwrap();
+		assert_eq!(trades[0].recipient, address(FEE_COLLECTOR));
+		assert_eq!(trades[0].token_out, Asset::Token(address(USDC)));
+	}
+
+	#[test]
+	fn leaves_router_balance_amounts_to_the_receipt() {
+		// A split route: part of the WETH through V3, the rest the router holds through V2
+		let tx = execute(&[0x0b, 0x00, 0x08], vec![
+			vec![placeholder(ADDRESS_THIS), Token::Uint(CONTRACT_BALANCE)],
+			vec![placeholder(MSG_SENDER), amount(600_000_000_000_000_000), amount(1_000_000_000), v3_path(WETH, 500, USDC), Token::Bool(false)],
+			vec![placeholder(MSG_SENDER), Token::Uint(CONTRACT_BALANCE), amount(700_000_000), Token::Array(vec![Token::Address(address(WETH)), Token::Address(address(USDC))]), Token::Bool(false)],
+		], 1_000_000_000_000_000_000);
+		let trades = decoder().decode_input(&tx).unwrap();
+
+		assert_eq!(trades.len(), 2);
+		assert_eq!(trades[0].amount_in, Some(U256::from(600_000_000_000_000_000u64)));
+		assert_eq!(trades[1].amount_in, None);
+		assert_eq!(trades[1].token_in, Asset::Native);
+		assert_eq!(trades[1].bound, U256::from(700_000_000u64));
+	}
+}
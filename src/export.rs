@@ -0,0 +1,331 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use web3::ethabi::Token;
+use web3::signing::keccak256;
+use web3::types::{Address, U256};
+
+use crate::batch::TxOutcome;
+use crate::decoders::universal_router::Step;
+use crate::fetch::parse_tx_hash;
+use crate::trade::{Asset, Trade};
+
+/// An address in EIP-55 mixed case checksum form.
+pub fn checksum(address: Address) -> String {
+	let hex = hex::encode(address.as_bytes());
+	let hash = keccak256(hex.as_bytes());
+	let mut checksummed = String::from("0x");
+	for (index, character) in hex.chars().enumerate() {
+		// Letters are upper cased where the matching nibble of the hash is 8 or more
+		let nibble = (hash[index / 2] >> (if index % 2 == 0 { 4 } else { 0 })) & 0x0f;
+		if nibble >= 8 {
+			checksummed.push(character.to_ascii_uppercase());
+		} else {
+			checksummed.push(character);
+		}
+	}
+	checksummed
+}
+
+fn asset(asset: Asset) -> String {
+	match asset {
+		Asset::Native => "native".to_owned(),
+		Asset::Token(token) => checksum(token),
+	}
+}
+
+fn enum_name<T: Serialize>(value: &T) -> String {
+	match serde_json::to_value(value) {
+		Ok(Value::String(name)) => name,
+		_ => String::new(),
+	}
+}
+
+/// A trade with checksummed addresses and `U256`s as decimal strings, for JSON and CSV.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TradeRecord {
+	pub chain: String,
+	pub dex: String,
+	pub protocol: String,
+	pub tx_hash: String,
+	pub trader: String,
+	pub recipient: String,
+	/// `native` for the chain's native coin
+	pub token_in: String,
+	pub token_out: String,
+	pub amount_in: Option<String>,
+	pub amount_out: Option<String>,
+	pub kind: String,
+	pub bound: String,
+	pub path: Vec<String>,
+	/// Fee tier of each V3 pool along the path, in hundredths of a bip
+	pub fees: Vec<u32>,
+	pub deadline: Option<String>,
+	pub executed_amount_in: Option<String>,
+	pub executed_amount_out: Option<String>,
+	pub allowed: Option<bool>,
+	pub violations: Vec<String>,
+}
+
+impl From<&Trade> for TradeRecord {
+	fn from(trade: &Trade) -> Self {
+		let decimal = |amount: U256| amount.to_string();
+		Self {
+			chain: trade.chain.to_string(),
+			dex: enum_name(&trade.dex),
+			protocol: enum_name(&trade.protocol),
+			tx_hash: format!("{:?}", trade.tx_hash),
+			trader: checksum(trade.trader),
+			recipient: checksum(trade.recipient),
+			token_in: asset(trade.token_in),
+			token_out: asset(trade.token_out),
+			amount_in: trade.amount_in.map(decimal),
+			amount_out: trade.amount_out.map(decimal),
+			kind: enum_name(&trade.kind),
+			bound: decimal(trade.bound),
+			path: trade.path.iter().map(|token| checksum(*token)).collect(),
+			fees: trade.route.iter().flat_map(|route| &route.hops).map(|hop| hop.fee).collect(),
+			deadline: trade.deadline.map(decimal),
+			executed_amount_in: trade.executed.map(|executed| decimal(executed.amount_in)),
+			executed_amount_out: trade.executed.map(|executed| decimal(executed.amount_out)),
+			allowed: trade.verdict.as_ref().map(|verdict| verdict.allowed),
+			violations: trade.verdict.iter()
+				.flat_map(|verdict| &verdict.violations)
+				.map(|violation| violation.to_string())
+				.collect(),
+		}
+	}
+}
+
+/// A decoded ABI value as JSON, with checksummed addresses, decimal integers and hex bytes.
+pub fn token_json(token: &Token) -> Value {
+	match token {
+		Token::Address(address) => Value::String(checksum(*address)),
+		Token::Uint(value) => Value::String(value.to_string()),
+		// Two's complement, so negative values print as their sign and magnitude
+		Token::Int(value) if value.bit(255) => Value::String(format!("-{}", (!*value).overflowing_add(U256::one()).0)),
+		Token::Int(value) => Value::String(value.to_string()),
+		Token::Bool(value) => Value::Bool(*value),
+		Token::String(value) => Value::String(value.clone()),
+		Token::Bytes(bytes) | Token::FixedBytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+		Token::Array(values) | Token::FixedArray(values) | Token::Tuple(values) => Value::Array(values.iter().map(token_json).collect()),
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepRecord {
+	pub index: usize,
+	pub command: u8,
+	pub name: String,
+	pub allow_revert: bool,
+	pub params: serde_json::Map<String, Value>,
+}
+
+impl From<&Step> for StepRecord {
+	fn from(step: &Step) -> Self {
+		Self {
+			index: step.index,
+			command: step.command,
+			name: step.name.to_owned(),
+			allow_revert: step.allow_revert,
+			params: step.params.iter().map(|(name, value)| (name.to_string(), token_json(value))).collect(),
+		}
+	}
+}
+
+/// Everything decoded from one transaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TxRecord {
+	pub hash: String,
+	pub status: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub block_number: Option<u64>,
+	/// Unix timestamp of the block
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub timestamp: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// Hash of the transaction that replaced a pending one
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub replaced_by: Option<String>,
+	pub trades: Vec<TradeRecord>,
+	/// Commands of a Universal Router call
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub steps: Vec<StepRecord>,
+}
+
+impl TxRecord {
+	/// The record of a transaction. Its hash is kept in lower-case hex, the way transactions
+	/// decoded from blocks are written, so the same transaction is stored once however its
+	/// hash was typed. A malformed hash is kept as given.
+	pub fn new(hash: &str, outcome: &TxOutcome, steps: &[Step]) -> Self {
+		let error = match outcome {
+			TxOutcome::Undecodable { error } | TxOutcome::Error { error } => Some(error.clone()),
+			_ => None,
+		};
+		Self {
+			hash: parse_tx_hash(hash).map(|hash| format!("{:?}", hash)).unwrap_or_else(|_| hash.to_owned()),
+			status: outcome.status().to_owned(),
+			block_number: None,
+			timestamp: None,
+			error,
+			replaced_by: match outcome {
+				TxOutcome::Replaced { by } => Some(format!("{:?}", by)),
+				_ => None,
+			},
+			trades: outcome.trades().iter().map(TradeRecord::from).collect(),
+			steps: steps.iter().map(StepRecord::from).collect(),
+		}
+	}
+
+	/// Records the block the transaction was mined in, if it was, without its time.
+	pub fn mined_in(mut self, block_number: Option<u64>) -> Self {
+		self.block_number = block_number;
+		self
+	}
+
+	/// Records the block the transaction was mined in.
+	pub fn at_block(mut self, number: u64, timestamp: u64) -> Self {
+		self.block_number = Some(number);
+		self.timestamp = Some(timestamp);
+		self
+	}
+}
+
+/// Formats a unix timestamp as an ISO 8601 UTC date and time.
+pub fn format_timestamp(timestamp: u64) -> String {
+	let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+	// Civil date from days since 1970-01-01, counting in 400 year eras starting on March 1st
+	let days = days as i64 + 719468;
+	let era = days.div_euclid(146097);
+	let day_of_era = days.rem_euclid(146097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_from_march = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+	let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+	format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+pub const CSV_HEADER: &str = "tx_hash,status,block_number,timestamp,leg,chain,dex,protocol,trader,recipient,token_in,token_out,kind,amount_in,amount_out,bound,executed_amount_in,executed_amount_out,deadline,path,fees,allowed,violations,error";
+
+fn csv_field(field: &str) -> String {
+	if field.contains([',', '"', '\n']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_owned()
+	}
+}
+
+/// One CSV row per trade leg of the transaction, or a single row with its status if it has none.
+pub fn csv_rows(record: &TxRecord) -> Vec<String> {
+	let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+	if record.trades.is_empty() {
+		let mut row = vec![record.hash.clone(), record.status.clone()];
+		row.extend(std::iter::repeat_n(String::new(), CSV_HEADER.split(',').count() - 3));
+		row.push(optional(&record.error));
+		return vec![row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",")];
+	}
+
+	record.trades.iter().enumerate().map(|(leg, trade)| {
+		let row = [
+			record.hash.clone(),
+			record.status.clone(),
+			optional(&record.block_number.map(|number| number.to_string())),
+			optional(&record.timestamp.map(|timestamp| timestamp.to_string())),
+			leg.to_string(),
+			trade.chain.clone(),
+			trade.dex.clone(),
+			trade.protocol.clone(),
+			trade.trader.clone(),
+			trade.recipient.clone(),
+			trade.token_in.clone(),
+			trade.token_out.clone(),
+			trade.kind.clone(),
+			optional(&trade.amount_in),
+			optional(&trade.amount_out),
+			trade.bound.clone(),
+			optional(&trade.executed_amount_in),
+			optional(&trade.executed_amount_out),
+			optional(&trade.deadline),
+			trade.path.join(" > "),
+			trade.fees.iter().map(|fee| fee.to_string()).collect::<Vec<_>>().join(" > "),
+			trade.allowed.map(|allowed| allowed.to_string()).unwrap_or_default(),
+			trade.violations.join("; "),
+			String::new(),
+		];
+		row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",")
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn checksums_the_eip_55_examples() {
+		for expected in [
+			"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+			"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+			"0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+			"0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+			// All upper and all lower case
+			"0x52908400098527886E0F7030069857D2E4169EE7",
+			"0xde709f2102306220921060314715629080e2fb77",
+		] {
+			let address: Address = expected.parse().unwrap();
+			assert_eq!(checksum(address), expected);
+		}
+	}
+
+	#[test]
+	fn quotes_csv_fields_with_commas_quotes_and_newlines() {
+		assert_eq!(csv_field("plain"), "plain");
+		assert_eq!(csv_field("a,b"), "\"a,b\"");
+		assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+		assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+	}
+
+	#[test]
+	fn quotes_errors_in_rows_without_trades() {
+		let outcome = TxOutcome::Error { error: "execution reverted: \"STF\", gas 21000".to_owned() };
+		let record = TxRecord::new("0xabc", &outcome, &[]);
+		let rows = csv_rows(&record);
+		assert_eq!(rows.len(), 1);
+		assert!(rows[0].starts_with("0xabc,error,"));
+		assert!(rows[0].ends_with(",\"execution reverted: \"\"STF\"\", gas 21000\""));
+		// A comma between each of the header's columns, plus the one inside the quoted error
+		assert_eq!(rows[0].matches(',').count(), CSV_HEADER.split(',').count());
+	}
+
+	#[test]
+	fn quotes_violations_in_trade_rows() {
+		let mut record = TxRecord::new("0xabc", &TxOutcome::Reverted, &[]).at_block(17_000_000, 1_681_000_000);
+		record.trades.push(TradeRecord {
+			chain: "eth".to_owned(),
+			dex: "uniswap".to_owned(),
+			protocol: "v2".to_owned(),
+			tx_hash: "0xabc".to_owned(),
+			trader: checksum(Address::repeat_byte(1)),
+			recipient: checksum(Address::repeat_byte(2)),
+			token_in: "native".to_owned(),
+			token_out: checksum(Address::repeat_byte(3)),
+			amount_in: Some("1000".to_owned()),
+			amount_out: None,
+			kind: "exact_in".to_owned(),
+			bound: "990".to_owned(),
+			path: vec![],
+			fees: vec![],
+			deadline: None,
+			executed_amount_in: None,
+			executed_amount_out: None,
+			allowed: Some(false),
+			violations: vec!["dex \"uniswap\" isn't allowed".to_owned(), "amount in 1,000 is over the limit".to_owned()],
+		});
+		let rows = csv_rows(&record);
+		assert_eq!(rows.len(), 1);
+		assert!(rows[0].starts_with("0xabc,reverted,17000000,1681000000,0,eth,"));
+		assert!(rows[0].ends_with(",false,\"dex \"\"uniswap\"\" isn't allowed; amount in 1,000 is over the limit\","));
+	}
+}
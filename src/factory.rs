@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::RwLock;
+
+use web3::ethabi::{self, Contract, RawLog, Token};
+use web3::{Transport, Web3};
+use web3::types::{Address, BlockNumber, Bytes, CallRequest, FilterBuilder, Transaction, U64};
+
+use crate::abi;
+use crate::decoders::Registry;
+use crate::trade::Chain;
+
+#[derive(Debug)]
+pub enum FactoryError {
+	Rpc(web3::Error),
+	Abi(ethabi::Error),
+}
+
+impl fmt::Display for FactoryError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FactoryError::Rpc(e) => write!(f, "rpc error: {}", e),
+			FactoryError::Abi(e) => write!(f, "failed to decode factory response: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for FactoryError {}
+
+impl From<web3::Error> for FactoryError {
+	fn from(e: web3::Error) -> Self {
+		FactoryError::Rpc(e)
+	}
+}
+
+impl From<ethabi::Error> for FactoryError {
+	fn from(e: ethabi::Error) -> Self {
+		FactoryError::Abi(e)
+	}
+}
+
+/// The Uniswap V1 factory, which created every V1 exchange and maps them to their tokens.
+pub struct UniswapV1Factory<T: Transport> {
+	web3: Web3<T>,
+	address: Address,
+	contract: Contract,
+	/// Addresses the factory didn't create, so each is only asked about once
+	not_exchanges: RwLock<HashSet<Address>>,
+}
+
+impl<T: Transport> UniswapV1Factory<T> {
+	pub fn new(web3: Web3<T>, address: Address) -> Result<Self, FactoryError> {
+		Ok(Self {
+			web3,
+			address,
+			contract: abi::load(abi::UNISWAP_V1_FACTORY)?,
+			not_exchanges: RwLock::new(HashSet::new()),
+		})
+	}
+
+	async fn call_address(&self, function_name: &str, argument: Address) -> Result<Option<Address>, FactoryError> {
+		let function = self.contract.function(function_name)?;
+		let data = function.encode_input(&[Token::Address(argument)])?;
+		let request = CallRequest::builder().to(self.address).data(Bytes(data)).build();
+		let output = self.web3.eth().call(request, None).await?;
+		// Addresses without code answer nothing at all
+		if output.0.is_empty() {
+			return Ok(None);
+		}
+		let address = function.decode_output(&output.0)?
+			.into_iter()
+			.next()
+			.and_then(Token::into_address);
+		// The factory answers the zero address for anything it didn't create
+		Ok(address.filter(|address| !address.is_zero()))
+	}
+
+	/// The token traded by `exchange`, `None` if it isn't a V1 exchange.
+	pub async fn token(&self, exchange: Address) -> Result<Option<Address>, FactoryError> {
+		self.call_address("getToken", exchange).await
+	}
+
+	/// Every exchange created between two blocks, as `(exchange, token)`, from the factory's
+	/// `NewExchange` events. Blocks are queried `step` at a time to stay under provider limits.
+	pub async fn index(&self, from_block: u64, to_block: u64, step: u64) -> Result<Vec<(Address, Address)>, FactoryError> {
+		let event = self.contract.event("NewExchange")?;
+		let mut exchanges: Vec<(Address, Address)> = Vec::new();
+
+		let mut start = from_block;
+		while start <= to_block {
+			let end = to_block.min(start + step.max(1) - 1);
+			let filter = FilterBuilder::default()
+				.address(vec![self.address])
+				.topics(Some(vec![event.signature()]), None, None, None)
+				.from_block(BlockNumber::Number(U64::from(start)))
+				.to_block(BlockNumber::Number(U64::from(end)))
+				.build();
+			for log in self.web3.eth().logs(filter).await? {
+				let parsed = event.parse_log(RawLog { topics: log.topics, data: log.data.0 })?;
+				let param = |name: &str| parsed.params.iter()
+					.find(|param| param.name == name)
+					.and_then(|param| param.value.clone().into_address());
+				if let (Some(exchange), Some(token)) = (param("exchange"), param("token")) {
+					exchanges.push((exchange, token));
+				}
+			}
+			start = end + 1;
+		}
+		Ok(exchanges)
+	}
+
+	/// Adds the V1 exchanges `tx` trades through that `registry` doesn't know yet, if the
+	/// factory created them. Returns how many were added.
+	///
+	/// Only V1 swap calls and plain ETH transfers are looked up, and an address the factory
+	/// didn't create is only looked up once.
+	pub async fn discover(&self, registry: &Registry, chain: Chain, tx: &Transaction) -> Result<usize, FactoryError> {
+		let (decoder, to) = match (registry.uniswap_v1(chain), tx.to) {
+			(Some(decoder), Some(to)) => (decoder, to),
+			_ => return Ok(0),
+		};
+		// Leave transactions to routers and other known contracts to their decoders
+		if registry.find(to, chain).is_some() && decoder.token(to).is_none() {
+			return Ok(0);
+		}
+
+		let mut added = 0;
+		for exchange in decoder.exchanges_called(tx) {
+			if decoder.token(exchange).is_some() || self.not_exchanges.read().unwrap().contains(&exchange) {
+				continue;
+			}
+			match self.token(exchange).await? {
+				Some(token) => {
+					decoder.add_exchange(exchange, token);
+					added += 1;
+				},
+				None => {
+					self.not_exchanges.write().unwrap().insert(exchange);
+				},
+			}
+		}
+		Ok(added)
+	}
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use regex::Regex;
+
+use web3::{Transport, Web3};
+use web3::types::{Block, BlockId, BlockNumber, Transaction, TransactionReceipt, TransactionId, H256, U64};
+
+#[derive(Debug)]
+pub enum FetchError {
+	MalformedHash(String),
+	NotFound(H256),
+	BlockNotFound(u64),
+	Pending(H256),
+	Reverted(H256),
+	Rpc(web3::Error),
+}
+
+impl fmt::Display for FetchError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FetchError::MalformedHash(hash) => write!(f, "malformed tx hash {}", hash),
+			FetchError::NotFound(hash) => write!(f, "tx {:?} doesn't exist", hash),
+			FetchError::BlockNotFound(number) => write!(f, "block {} doesn't exist yet", number),
+			FetchError::Pending(hash) => write!(f, "tx {:?} is still pending", hash),
+			FetchError::Reverted(hash) => write!(f, "tx {:?} was reverted", hash),
+			FetchError::Rpc(e) => write!(f, "rpc error: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<web3::Error> for FetchError {
+	fn from(e: web3::Error) -> Self {
+		FetchError::Rpc(e)
+	}
+}
+
+/// Parses a `0x`-prefixed, 32 byte hex transaction hash.
+pub fn parse_tx_hash(hash: &str) -> Result<H256, FetchError> {
+	let re = Regex::new(r"^0x[0-9a-fA-F]{64}$").unwrap();
+	if !re.is_match(hash) {
+		return Err(FetchError::MalformedHash(hash.to_owned()));
+	}
+	hash.parse().map_err(|_| FetchError::MalformedHash(hash.to_owned()))
+}
+
+/// Fetches a transaction and its receipt, succeeding only if the transaction
+/// was mined and ran successfully.
+///
+/// A transaction without a `block_number` is pending, and one whose receipt
+/// has a `status` other than `1` was reverted.
+pub async fn fetch_confirmed_tx<T: Transport>(web3: &Web3<T>, hash: &str) -> Result<(Transaction, TransactionReceipt), FetchError> {
+	match fetch_tx(web3, hash).await? {
+		(tx, Some(receipt)) => Ok((tx, receipt)),
+		(tx, None) => Err(FetchError::Pending(tx.hash)),
+	}
+}
+
+/// Fetches a transaction, and its receipt once it's mined, failing if the transaction
+/// doesn't exist or was reverted. Pending transactions come without a receipt.
+pub async fn fetch_tx<T: Transport>(web3: &Web3<T>, hash: &str) -> Result<(Transaction, Option<TransactionReceipt>), FetchError> {
+	let hash = parse_tx_hash(hash)?;
+
+	let tx = match web3.eth().transaction(TransactionId::Hash(hash)).await? {
+		Some(tx) => tx,
+		None => return Err(FetchError::NotFound(hash)),
+	};
+	let receipt = fetch_receipt(web3, &tx).await?;
+	Ok((tx, receipt))
+}
+
+/// Fetches the receipt of a transaction once it's mined, failing if the transaction was reverted.
+/// Pending transactions have no receipt yet.
+pub async fn fetch_receipt<T: Transport>(web3: &Web3<T>, tx: &Transaction) -> Result<Option<TransactionReceipt>, FetchError> {
+	if tx.block_number.is_none() {
+		return Ok(None);
+	}
+
+	// To check if a transaction was reverted or successful, you need to check the receipt of the transaction
+	let receipt = match web3.eth().transaction_receipt(tx.hash).await? {
+		Some(receipt) => receipt,
+		None => return Err(FetchError::NotFound(tx.hash)),
+	};
+	if receipt.status != Some(U64::from(1)) {
+		return Err(FetchError::Reverted(tx.hash));
+	}
+
+	Ok(Some(receipt))
+}
+
+/// Fetches a mined block with its transactions.
+pub async fn fetch_block<T: Transport>(web3: &Web3<T>, number: u64) -> Result<Block<Transaction>, FetchError> {
+	web3.eth().block_with_txs(BlockId::Number(BlockNumber::Number(U64::from(number)))).await?
+		.ok_or(FetchError::BlockNotFound(number))
+}
+
+/// Fetches the transactions of a mined block.
+pub async fn fetch_block_txs<T: Transport>(web3: &Web3<T>, number: u64) -> Result<Vec<Transaction>, FetchError> {
+	Ok(fetch_block(web3, number).await?.transactions)
+}
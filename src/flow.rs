@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use web3::types::{Address, Transaction, TransactionReceipt, U256};
+
+use crate::logs::{self, Event};
+use crate::trade::{Asset, Chain, Dex, Executed, Protocol, Trade, TradeKind};
+
+/// How much of one asset a wallet received and sent in a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetFlow {
+	pub asset: Asset,
+	pub received: U256,
+	pub sent: U256,
+}
+
+impl NetFlow {
+	pub fn is_gain(&self) -> bool {
+		self.received > self.sent
+	}
+
+	pub fn is_loss(&self) -> bool {
+		self.sent > self.received
+	}
+
+	/// Size of the balance change, in whichever direction it went.
+	pub fn amount(&self) -> U256 {
+		if self.is_gain() { self.received - self.sent } else { self.sent - self.received }
+	}
+}
+
+/// An asset changing hands, in the order the transaction moved it.
+struct Movement {
+	asset: Asset,
+	from: Address,
+	to: Address,
+	value: U256,
+	/// Native coin paid out of WETH / WBNB to whoever unwrapped it
+	unwrapped: bool,
+}
+
+/// Every asset movement visible in a transaction: the value sent with it, ERC-20 transfers,
+/// and wrapped native coin minted by deposits and burned by withdrawals.
+fn movements(tx: &Transaction, receipt: &TransactionReceipt) -> Vec<Movement> {
+	let mut movements: Vec<Movement> = Vec::new();
+	if !tx.value.is_zero() {
+		movements.push(Movement { asset: Asset::Native, from: tx.from.unwrap_or_default(), to: tx.to.unwrap_or_default(), value: tx.value, unwrapped: false });
+	}
+
+	for log in logs::decode_logs(receipt) {
+		match log.event {
+			Event::Transfer { token, from, to, value } => {
+				movements.push(Movement { asset: Asset::Token(token), from, to, value, unwrapped: false });
+			},
+			// The native coin paid for the deposit is already counted in the value sent with the transaction
+			Event::Deposit { token, owner, value } => {
+				movements.push(Movement { asset: Asset::Token(token), from: Address::zero(), to: owner, value, unwrapped: false });
+			},
+			Event::Withdrawal { token, owner, value } => {
+				movements.push(Movement { asset: Asset::Token(token), from: owner, to: Address::zero(), value, unwrapped: false });
+				movements.push(Movement { asset: Asset::Native, from: token, to: owner, value, unwrapped: true });
+			},
+			_ => {},
+		}
+	}
+	movements
+}
+
+fn flows_of(movements: &[Movement], wallet: Address) -> Vec<NetFlow> {
+	let mut flows: Vec<NetFlow> = Vec::new();
+	for movement in movements.iter().filter(|movement| movement.from != movement.to) {
+		if movement.from != wallet && movement.to != wallet {
+			continue;
+		}
+		let index = match flows.iter().position(|flow| flow.asset == movement.asset) {
+			Some(index) => index,
+			None => {
+				flows.push(NetFlow { asset: movement.asset, received: U256::zero(), sent: U256::zero() });
+				flows.len() - 1
+			},
+		};
+		if movement.to == wallet {
+			flows[index].received = flows[index].received.saturating_add(movement.value);
+		} else {
+			flows[index].sent = flows[index].sent.saturating_add(movement.value);
+		}
+	}
+	flows
+}
+
+/// Net balance change of every asset `wallet` touched in the transaction, in the order it first moved.
+pub fn net_flows(tx: &Transaction, receipt: &TransactionReceipt, wallet: Address) -> Vec<NetFlow> {
+	flows_of(&movements(tx, receipt), wallet)
+}
+
+/// Best-effort trade reconstructed from what the caller gave and what they, or whoever
+/// received the last payout, got back. Works for any router, but only sees assets that
+/// moved through logs or the transaction's value.
+///
+/// Native coin paid out of WETH / WBNB to a router is assumed to be forwarded to the
+/// caller, since internal transfers aren't logged.
+pub fn trade(chain: Chain, tx: &Transaction, receipt: &TransactionReceipt) -> Option<Trade> {
+	let movements = movements(tx, receipt);
+	let trader = tx.from.unwrap_or_default();
+	let trader_flows = flows_of(&movements, trader);
+	let given = *trader_flows.iter().find(|flow| flow.is_loss())?;
+
+	let (recipient, received) = match trader_flows.iter().find(|flow| flow.is_gain()) {
+		Some(received) => (trader, *received),
+		None => {
+			let payout = movements.iter().rev().find(|movement| movement.from != trader && movement.asset != given.asset)?;
+			let recipient = if payout.unwrapped { trader } else { payout.to };
+			let received = if payout.unwrapped {
+				NetFlow { asset: Asset::Native, received: payout.value, sent: U256::zero() }
+			} else {
+				*flows_of(&movements, recipient).iter().find(|flow| flow.asset == payout.asset && flow.is_gain())?
+			};
+			(recipient, received)
+		},
+	};
+
+	Some(Trade {
+		chain,
+		dex: Dex::Unknown,
+		protocol: Protocol::Unknown,
+		tx_hash: tx.hash,
+		trader,
+		recipient,
+		token_in: given.asset,
+		token_out: received.asset,
+		amount_in: Some(given.amount()),
+		amount_out: Some(received.amount()),
+		// Only the outcome is known, not which side the caller fixed or the slippage allowed
+		kind: TradeKind::ExactIn,
+		bound: U256::zero(),
+		// The route is unknown
+		path: Vec::new(),
+		route: None,
+		deadline: None,
+		executed: Some(Executed { amount_in: given.amount(), amount_out: received.amount() }),
+		verdict: None,
+	})
+}
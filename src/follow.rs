@@ -0,0 +1,98 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use futures::{stream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use web3::Web3;
+use web3::api::SubscriptionStream;
+use web3::transports::WebSocket;
+use web3::types::{BlockHeader, H256};
+
+/// How long to wait before reconnecting a dropped WebSocket, by default
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How long a subscription may go without a notification before it's reconnected, by default
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Errors of a WebSocket subscription, after which it reconnects.
+#[derive(Debug)]
+pub enum FollowError {
+	Rpc(web3::Error),
+	/// The socket closed
+	Closed,
+	/// Nothing came for this long
+	Idle(Duration),
+}
+
+impl fmt::Display for FollowError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FollowError::Rpc(e) => write!(f, "websocket error: {}", e),
+			FollowError::Closed => write!(f, "websocket closed"),
+			FollowError::Idle(idle) => write!(f, "no notification for {}s", idle.as_secs()),
+		}
+	}
+}
+
+impl std::error::Error for FollowError {}
+
+impl From<web3::Error> for FollowError {
+	fn from(e: web3::Error) -> Self {
+		FollowError::Rpc(e)
+	}
+}
+
+/// Notifications of the subscription `subscribe` makes on `url`. The stream never ends: when
+/// the subscription fails, the socket drops or nothing comes for `idle`, it yields the error
+/// and then reconnects, `retry` after any failed attempt.
+fn resubscribing<N, S, F>(url: String, retry: Duration, idle: Duration, subscribe: S) -> impl Stream<Item = Result<N, FollowError>>
+where
+	N: DeserializeOwned,
+	S: Fn(Web3<WebSocket>) -> F + Clone,
+	F: Future<Output = web3::Result<SubscriptionStream<WebSocket, N>>>,
+{
+	stream::unfold((None, false), move |(notifications, failed): (Option<SubscriptionStream<WebSocket, N>>, bool)| {
+		let (url, subscribe) = (url.clone(), subscribe.clone());
+		async move {
+			let mut notifications = match notifications {
+				Some(notifications) => notifications,
+				None => {
+					if failed {
+						tokio::time::sleep(retry).await;
+					}
+					let subscribed = match WebSocket::new(&url).await {
+						Ok(transport) => subscribe(Web3::new(transport)).await,
+						Err(e) => Err(e),
+					};
+					match subscribed {
+						Ok(notifications) => notifications,
+						Err(e) => return Some((Err(e.into()), (None, true))),
+					}
+				},
+			};
+			match tokio::time::timeout(idle, notifications.next()).await {
+				Ok(Some(Ok(notification))) => Some((Ok(notification), (Some(notifications), false))),
+				Ok(Some(Err(e))) => Some((Err(e.into()), (None, true))),
+				Ok(None) => Some((Err(FollowError::Closed), (None, true))),
+				Err(_) => Some((Err(FollowError::Idle(idle)), (None, true))),
+			}
+		}
+	})
+}
+
+/// Heads of new blocks from an `eth_subscribe("newHeads")` subscription on `url`, as they're
+/// mined. The stream never ends: errors are yielded and the subscription is made again, `retry`
+/// after a failed attempt, when the socket drops or no head comes for `idle`.
+///
+/// Heads mined while reconnecting are missed, so followers should catch up from the last
+/// block they saw.
+pub fn new_heads(url: String, retry: Duration, idle: Duration) -> impl Stream<Item = Result<BlockHeader, FollowError>> {
+	resubscribing(url, retry, idle, |web3: Web3<WebSocket>| async move { web3.eth_subscribe().subscribe_new_heads().await })
+}
+
+/// Hashes of transactions entering the mempool, from an `eth_subscribe("newPendingTransactions")`
+/// subscription on `url`. Like `new_heads`, it reconnects instead of ending.
+pub fn pending_transactions(url: String, retry: Duration, idle: Duration) -> impl Stream<Item = Result<H256, FollowError>> {
+	resubscribing(url, retry, idle, |web3: Web3<WebSocket>| async move { web3.eth_subscribe().subscribe_new_pending_transactions().await })
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use web3::Transport;
+use web3::types::{Address, BlockId, BlockNumber, TransactionId, H256, U64};
+
+use crate::batch::TxOutcome;
+use crate::fetch::FetchError;
+use crate::tracker::{TrackError, Tracker};
+use crate::trade::Trade;
+
+/// A transaction of a wallet's history and the trades it made for the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletTx {
+	pub hash: H256,
+	/// Unknown when the transaction couldn't be fetched
+	pub block_number: Option<u64>,
+	/// Unix timestamp of the block
+	pub timestamp: Option<u64>,
+	/// `Success` with the wallet's trades, or why the transaction couldn't be fetched
+	pub outcome: TxOutcome,
+}
+
+/// Decodes the transactions `hashes`, given in chain order, keeping the trades `wallet` made or
+/// was paid by. Transactions without such trades, reverted ones and ones the decoders can't
+/// make sense of are left out. One that can't be fetched is kept with the error, and doesn't
+/// stop the others.
+pub async fn decode_wallet_txs<T: Transport>(tracker: &Tracker<T>, wallet: Address, hashes: &[H256]) -> Vec<WalletTx> {
+	let mut timestamps: HashMap<u64, u64> = HashMap::new();
+	let mut txs: Vec<WalletTx> = Vec::new();
+	for &hash in hashes {
+		match decode_wallet_tx(tracker, wallet, hash, &mut timestamps).await {
+			Ok(Some(tx)) => txs.push(tx),
+			Ok(None) => {},
+			Err(e) => txs.push(WalletTx { hash, block_number: None, timestamp: None, outcome: Err(e).into() }),
+		}
+	}
+	txs
+}
+
+/// Decodes one transaction of `wallet`'s history, `None` if it's left out. Block timestamps
+/// are cached in `timestamps`.
+async fn decode_wallet_tx<T: Transport>(tracker: &Tracker<T>, wallet: Address, hash: H256, timestamps: &mut HashMap<u64, u64>) -> Result<Option<WalletTx>, TrackError> {
+	let tx = match tracker.web3.eth().transaction(TransactionId::Hash(hash)).await.map_err(FetchError::from)? {
+		Some(tx) => tx,
+		None => return Err(FetchError::NotFound(hash).into()),
+	};
+	let block_number = match tx.block_number {
+		Some(number) => number.as_u64(),
+		None => return Ok(None),
+	};
+	let receipt = match tracker.web3.eth().transaction_receipt(hash).await.map_err(FetchError::from)? {
+		Some(receipt) if receipt.status == Some(U64::from(1)) => receipt,
+		_ => return Ok(None),
+	};
+
+	let trades: Vec<Trade> = match tracker.decode(&tx, &receipt).await {
+		Ok(trades) => trades.into_iter()
+			.filter(|trade| tx.from == Some(wallet) || trade.trader == wallet || trade.recipient == wallet)
+			.collect(),
+		Err(TrackError::Decode(_)) => return Ok(None),
+		Err(e) => return Err(e),
+	};
+	if trades.is_empty() {
+		return Ok(None);
+	}
+
+	let timestamp = match timestamps.get(&block_number) {
+		Some(&timestamp) => timestamp,
+		None => {
+			let block = tracker.web3.eth().block(BlockId::Number(BlockNumber::Number(U64::from(block_number)))).await.map_err(FetchError::from)?;
+			let timestamp = block.ok_or(FetchError::BlockNotFound(block_number))?.timestamp.as_u64();
+			timestamps.insert(block_number, timestamp);
+			timestamp
+		},
+	};
+	Ok(Some(WalletTx { hash, block_number: Some(block_number), timestamp: Some(timestamp), outcome: TxOutcome::Success { trades } }))
+}
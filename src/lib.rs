@@ -0,0 +1,41 @@
+// ABI decoding goes through the ethabi version re-exported by web3, so decoded
+// addresses and amounts share their types with web3's transactions and receipts
+pub mod abi;
+pub mod addresses;
+pub mod batch;
+pub mod config;
+pub mod decoders;
+pub mod export;
+pub mod factory;
+pub mod fetch;
+pub mod flow;
+pub mod follow;
+pub mod history;
+pub mod logs;
+pub mod mempool;
+pub mod path;
+pub mod policy;
+pub mod reorg;
+pub mod scanner;
+pub mod store;
+pub mod tokens;
+pub mod tracker;
+pub mod trade;
+
+pub use addresses::{AddressBook, AddressBookError, ChainAddresses};
+pub use config::{Config, ConfigError, ServeConfig};
+pub use decoders::{DecodeError, DexDecoder, Registry};
+pub use factory::{FactoryError, UniswapV1Factory};
+pub use fetch::{fetch_confirmed_tx, fetch_receipt, fetch_tx, FetchError};
+pub use flow::NetFlow;
+pub use follow::FollowError;
+pub use history::WalletTx;
+pub use mempool::{Mempool, PendingTx, Settled};
+pub use path::{Hop, Path};
+pub use policy::{Policy, Verdict, Violation};
+pub use reorg::ReorgWindow;
+pub use scanner::{LogRange, LogScanner, TopicFilter};
+pub use store::{FailedRange, Store, StoreError, StoredTrade, TradeQuery};
+pub use tokens::{ResolveError, TokenMetadata, TokenResolver};
+pub use tracker::{BlockTrades, TrackError, Tracker};
+pub use trade::{Asset, Chain, Dex, Executed, Protocol, Trade, TradeKind};
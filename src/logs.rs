@@ -0,0 +1,412 @@
+use std::sync::OnceLock;
+
+use web3::ethabi::{self, EventParam, ParamType, RawLog, Token};
+use web3::types::{Address, Log, TransactionReceipt, H256, U256};
+
+use crate::abi;
+use crate::trade::{Asset, Executed, Trade};
+
+/// A pool balance change as logged by V3 pools, positive when the pool receives tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolDelta {
+	pub negative: bool,
+	pub amount: U256,
+}
+
+impl PoolDelta {
+	/// Reads a two's complement `int256`.
+	fn from_int(value: U256) -> Self {
+		if value.bit(255) {
+			Self { negative: true, amount: (!value).overflowing_add(U256::one()).0 }
+		} else {
+			Self { negative: false, amount: value }
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+	/// Uniswap V1 ETH to token swap
+	TokenPurchase { exchange: Address, buyer: Address, eth_sold: U256, tokens_bought: U256 },
+	/// Uniswap V1 token to ETH swap
+	EthPurchase { exchange: Address, buyer: Address, tokens_sold: U256, eth_bought: U256 },
+	/// Uniswap V2 style pair swap
+	V2Swap { pair: Address, sender: Address, to: Address, amount0_in: U256, amount1_in: U256, amount0_out: U256, amount1_out: U256 },
+	/// Uniswap V2 style pair reserves after a swap
+	V2Sync { pair: Address, reserve0: U256, reserve1: U256 },
+	/// Uniswap V3 / PancakeSwap V3 pool swap
+	V3Swap { pool: Address, sender: Address, recipient: Address, amount0: PoolDelta, amount1: PoolDelta },
+	/// ERC-20 transfer
+	Transfer { token: Address, from: Address, to: Address, value: U256 },
+	/// Native coin wrapped into WETH / WBNB
+	Deposit { token: Address, owner: Address, value: U256 },
+	/// WETH / WBNB unwrapped into the native coin
+	Withdrawal { token: Address, owner: Address, value: U256 },
+}
+
+/// A receipt log decoded into one of the events above.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedLog {
+	pub log_index: Option<U256>,
+	pub event: Event,
+}
+
+struct Events {
+	token_purchase: ethabi::Event,
+	eth_purchase: ethabi::Event,
+	v2_swap: ethabi::Event,
+	v2_sync: ethabi::Event,
+	uniswap_v3_swap: ethabi::Event,
+	pancakeswap_v3_swap: ethabi::Event,
+	transfer: ethabi::Event,
+	deposit: ethabi::Event,
+	withdrawal: ethabi::Event,
+}
+
+/// Events from the bundled ABIs, and the V3 pool swaps whose ABIs aren't bundled.
+fn events() -> &'static Events {
+	static EVENTS: OnceLock<Events> = OnceLock::new();
+	EVENTS.get_or_init(|| {
+		let exchange = abi::load(abi::UNISWAP_V1_EXCHANGE).expect("Error: Failed to parse bundled ABI");
+		let pair = abi::load(abi::UNISWAP_V2_PAIR).expect("Error: Failed to parse bundled ABI");
+		let weth = abi::load(abi::WETH).expect("Error: Failed to parse bundled ABI");
+		let event = |contract: &ethabi::Contract, name: &str| contract.event(name).expect("Error: Missing event in bundled ABI").clone();
+
+		let param = |name: &str, kind: ParamType, indexed: bool| EventParam { name: name.to_owned(), kind, indexed };
+		let mut v3_swap_params = vec![
+			param("sender", ParamType::Address, true),
+			param("recipient", ParamType::Address, true),
+			param("amount0", ParamType::Int(256), false),
+			param("amount1", ParamType::Int(256), false),
+			param("sqrtPriceX96", ParamType::Uint(160), false),
+			param("liquidity", ParamType::Uint(128), false),
+			param("tick", ParamType::Int(24), false),
+		];
+		let uniswap_v3_swap = ethabi::Event { name: "Swap".to_owned(), inputs: v3_swap_params.clone(), anonymous: false };
+		v3_swap_params.push(param("protocolFeesToken0", ParamType::Uint(128), false));
+		v3_swap_params.push(param("protocolFeesToken1", ParamType::Uint(128), false));
+		let pancakeswap_v3_swap = ethabi::Event { name: "Swap".to_owned(), inputs: v3_swap_params, anonymous: false };
+
+		Events {
+			token_purchase: event(&exchange, "TokenPurchase"),
+			eth_purchase: event(&exchange, "EthPurchase"),
+			v2_swap: event(&pair, "Swap"),
+			v2_sync: event(&pair, "Sync"),
+			uniswap_v3_swap,
+			pancakeswap_v3_swap,
+			transfer: event(&pair, "Transfer"),
+			deposit: event(&weth, "Deposit"),
+			withdrawal: event(&weth, "Withdrawal"),
+		}
+	})
+}
+
+fn parse(event: &ethabi::Event, log: &Log) -> Option<Vec<Token>> {
+	if log.topics.first() != Some(&event.signature()) {
+		return None;
+	}
+	let raw = RawLog { topics: log.topics.clone(), data: log.data.0.clone() };
+	event.parse_log(raw).ok().map(|parsed| parsed.params.into_iter().map(|param| param.value).collect())
+}
+
+/// Decodes a log into a known event, `None` for any other log.
+pub fn decode_log(log: &Log) -> Option<Event> {
+	let events = events();
+	let uint = |value: &Token| value.clone().into_uint().unwrap_or_default();
+	let int = |value: &Token| PoolDelta::from_int(value.clone().into_int().unwrap_or_default());
+	let address = |value: &Token| value.clone().into_address().unwrap_or_default();
+
+	if let Some(values) = parse(&events.token_purchase, log) {
+		return Some(Event::TokenPurchase { exchange: log.address, buyer: address(&values[0]), eth_sold: uint(&values[1]), tokens_bought: uint(&values[2]) });
+	}
+	if let Some(values) = parse(&events.eth_purchase, log) {
+		return Some(Event::EthPurchase { exchange: log.address, buyer: address(&values[0]), tokens_sold: uint(&values[1]), eth_bought: uint(&values[2]) });
+	}
+	if let Some(values) = parse(&events.v2_swap, log) {
+		return Some(Event::V2Swap {
+			pair: log.address,
+			sender: address(&values[0]),
+			amount0_in: uint(&values[1]),
+			amount1_in: uint(&values[2]),
+			amount0_out: uint(&values[3]),
+			amount1_out: uint(&values[4]),
+			to: address(&values[5]),
+		});
+	}
+	if let Some(values) = parse(&events.v2_sync, log) {
+		return Some(Event::V2Sync { pair: log.address, reserve0: uint(&values[0]), reserve1: uint(&values[1]) });
+	}
+	if let Some(values) = parse(&events.uniswap_v3_swap, log).or_else(|| parse(&events.pancakeswap_v3_swap, log)) {
+		return Some(Event::V3Swap { pool: log.address, sender: address(&values[0]), recipient: address(&values[1]), amount0: int(&values[2]), amount1: int(&values[3]) });
+	}
+	// ERC-721 transfers share the signature, but index the token id as a 4th topic and fail to parse here
+	if let Some(values) = parse(&events.transfer, log) {
+		return Some(Event::Transfer { token: log.address, from: address(&values[0]), to: address(&values[1]), value: uint(&values[2]) });
+	}
+	if let Some(values) = parse(&events.deposit, log) {
+		return Some(Event::Deposit { token: log.address, owner: address(&values[0]), value: uint(&values[1]) });
+	}
+	if let Some(values) = parse(&events.withdrawal, log) {
+		return Some(Event::Withdrawal { token: log.address, owner: address(&values[0]), value: uint(&values[1]) });
+	}
+	None
+}
+
+/// Topics of the swap events of V1 exchanges, V2 pairs and V3 pools, for `eth_getLogs` filters.
+pub fn swap_topics() -> Vec<H256> {
+	let events = events();
+	[&events.token_purchase, &events.eth_purchase, &events.v2_swap, &events.uniswap_v3_swap, &events.pancakeswap_v3_swap]
+		.iter()
+		.map(|event| event.signature())
+		.collect()
+}
+
+/// Event topics by where they put the wallet on the paying or the receiving side.
+pub struct WalletTopics {
+	/// Events with the wallet as their first indexed address: transfers from it and V1 purchases by it
+	pub sender: Vec<H256>,
+	/// Events with the wallet as their second indexed address: transfers to it and swaps paying out to it
+	pub recipient: Vec<H256>,
+}
+
+/// Topics of the events that name a wallet trading, for `eth_getLogs` filters.
+pub fn wallet_topics() -> WalletTopics {
+	let events = events();
+	WalletTopics {
+		sender: vec![events.transfer.signature(), events.token_purchase.signature(), events.eth_purchase.signature()],
+		recipient: vec![events.transfer.signature(), events.v2_swap.signature(), events.uniswap_v3_swap.signature(), events.pancakeswap_v3_swap.signature()],
+	}
+}
+
+/// Decodes every known event in a receipt, in log order.
+pub fn decode_logs(receipt: &TransactionReceipt) -> Vec<DecodedLog> {
+	receipt.logs.iter()
+		.filter_map(|log| decode_log(log).map(|event| DecodedLog { log_index: log.log_index, event }))
+		.collect()
+}
+
+/// What went in and out of a single pool during a swap.
+struct Leg {
+	token_in: Option<Asset>,
+	amount_in: U256,
+	token_out: Option<Asset>,
+	amount_out: U256,
+}
+
+/// Turns the swap events of a receipt into legs, resolving the tokens each pool swapped
+/// from the transfers into and out of it.
+fn legs(events: &[Event]) -> Vec<Leg> {
+	let transferred = |pool_from: Option<Address>, pool_to: Option<Address>, amount: U256| events.iter().find_map(|event| match event {
+		Event::Transfer { token, from, to, value } if *value == amount && pool_from.is_none_or(|pool| *from == pool) && pool_to.is_none_or(|pool| *to == pool) => {
+			Some(Asset::Token(*token))
+		},
+		_ => None,
+	});
+
+	events.iter().filter_map(|event| {
+		let (pool, amount_in, amount_out, native_in, native_out) = match event {
+			Event::TokenPurchase { exchange, eth_sold, tokens_bought, .. } => (*exchange, *eth_sold, *tokens_bought, true, false),
+			Event::EthPurchase { exchange, tokens_sold, eth_bought, .. } => (*exchange, *tokens_sold, *eth_bought, false, true),
+			Event::V2Swap { pair, amount0_in, amount1_in, amount0_out, amount1_out, .. } => {
+				let amount_in = if amount0_in.is_zero() { *amount1_in } else { *amount0_in };
+				let amount_out = if amount0_out.is_zero() { *amount1_out } else { *amount0_out };
+				(*pair, amount_in, amount_out, false, false)
+			},
+			Event::V3Swap { pool, amount0, amount1, .. } => {
+				let (amount_in, amount_out) = if amount0.negative { (amount1.amount, amount0.amount) } else { (amount0.amount, amount1.amount) };
+				(*pool, amount_in, amount_out, false, false)
+			},
+			_ => return None,
+		};
+		Some(Leg {
+			token_in: if native_in { Some(Asset::Native) } else { transferred(None, Some(pool), amount_in) },
+			amount_in,
+			token_out: if native_out { Some(Asset::Native) } else { transferred(Some(pool), None, amount_out) },
+			amount_out,
+		})
+	}).collect()
+}
+
+/// Fills in what each trade actually swapped, from the swap events in its receipt.
+///
+/// Trades are matched in order to the first leg spending their token in, and the leg
+/// after it receiving their token out. Native coin legs of V2 and V3 swaps go through
+/// `weth`, the chain's wrapped native coin.
+pub fn fill_executed(trades: &mut [Trade], receipt: &TransactionReceipt, weth: Option<Address>) {
+	let events: Vec<Event> = decode_logs(receipt).into_iter().map(|log| log.event).collect();
+	let legs = legs(&events);
+	let same = |asset: Asset, leg_asset: Option<Asset>| match (asset, leg_asset) {
+		(asset, Some(leg_asset)) if asset == leg_asset => true,
+		(Asset::Native, Some(Asset::Token(token))) => Some(token) == weth,
+		_ => false,
+	};
+
+	let mut next = 0;
+	for trade in trades.iter_mut() {
+		let first = match (next..legs.len()).find(|&index| same(trade.token_in, legs[index].token_in)) {
+			Some(first) => first,
+			None => continue,
+		};
+		let last = match (first..legs.len()).find(|&index| same(trade.token_out, legs[index].token_out)) {
+			Some(last) => last,
+			None => continue,
+		};
+		trade.executed = Some(Executed {
+			amount_in: legs[first].amount_in,
+			amount_out: legs[last].amount_out,
+		});
+		next = last + 1;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::{Bytes, H256};
+
+	use super::*;
+	use crate::trade::{Chain, Dex, Protocol, TradeKind};
+
+	const TRADER: u64 = 0x7;
+	const ROUTER: u64 = 0xe0;
+	const USDC: u64 = 0x10;
+	const WETH: u64 = 0x11;
+	const DAI: u64 = 0x12;
+
+	fn address(address: u64) -> Address {
+		Address::from_low_u64_be(address)
+	}
+
+	fn log(contract: u64, event: &ethabi::Event, indexed: &[u64], data: &[Token]) -> Log {
+		let mut topics = vec![event.signature()];
+		topics.extend(indexed.iter().map(|address| H256::from(self::address(*address))));
+		Log {
+			address: address(contract),
+			topics,
+			data: Bytes(ethabi::encode(data)),
+			block_hash: None,
+			block_number: None,
+			transaction_hash: None,
+			transaction_index: None,
+			log_index: None,
+			transaction_log_index: None,
+			log_type: None,
+			removed: None,
+		}
+	}
+
+	fn transfer(token: u64, from: u64, to: u64, value: u128) -> Log {
+		log(token, &events().transfer, &[from, to], &[Token::Uint(value.into())])
+	}
+
+	fn v2_swap(pair: u64, amounts_in: (u128, u128), amounts_out: (u128, u128), to: u64) -> Log {
+		let amounts = [amounts_in.0, amounts_in.1, amounts_out.0, amounts_out.1].map(|amount| Token::Uint(amount.into()));
+		log(pair, &events().v2_swap, &[ROUTER, to], &amounts)
+	}
+
+	/// A V3 swap with the pool's balance changes, positive for what it received.
+	fn v3_swap(pool: u64, amount0: i128, amount1: i128, recipient: u64) -> Log {
+		let int = |amount: i128| match amount {
+			amount if amount < 0 => Token::Int((!U256::from(amount.unsigned_abs())).overflowing_add(U256::one()).0),
+			amount => Token::Int(U256::from(amount as u128)),
+		};
+		let data = [int(amount0), int(amount1), Token::Uint(U256::one()), Token::Uint(U256::one()), Token::Int(U256::zero())];
+		log(pool, &events().uniswap_v3_swap, &[ROUTER, recipient], &data)
+	}
+
+	fn receipt(logs: Vec<Log>) -> TransactionReceipt {
+		TransactionReceipt { logs, ..Default::default() }
+	}
+
+	fn trade(token_in: Asset, token_out: Asset, kind: TradeKind) -> Trade {
+		Trade {
+			chain: Chain::Ethereum,
+			dex: Dex::Uniswap,
+			protocol: Protocol::V2,
+			tx_hash: H256::zero(),
+			trader: address(TRADER),
+			recipient: address(TRADER),
+			token_in,
+			token_out,
+			amount_in: None,
+			amount_out: None,
+			kind,
+			bound: U256::zero(),
+			path: Vec::new(),
+			route: None,
+			deadline: None,
+			executed: None,
+			verdict: None,
+		}
+	}
+
+	fn executed(trade: &Trade) -> Option<(u128, u128)> {
+		trade.executed.map(|executed| (executed.amount_in.as_u128(), executed.amount_out.as_u128()))
+	}
+
+	#[test]
+	fn fills_a_multi_hop_v2_swap_from_its_first_and_last_pair() {
+		// USDC to WETH through pair 0x20, then WETH to DAI through pair 0x21
+		let receipt = receipt(vec![
+			transfer(USDC, TRADER, 0x20, 1_000_000_000),
+			transfer(WETH, 0x20, 0x21, 500_000_000_000_000_000),
+			v2_swap(0x20, (1_000_000_000, 0), (0, 500_000_000_000_000_000), 0x21),
+			transfer(DAI, 0x21, TRADER, 990_000_000_000_000_000_000),
+			v2_swap(0x21, (0, 500_000_000_000_000_000), (990_000_000_000_000_000_000, 0), TRADER),
+		]);
+		let mut trades = vec![trade(Asset::Token(address(USDC)), Asset::Token(address(DAI)), TradeKind::ExactIn)];
+		fill_executed(&mut trades, &receipt, Some(address(WETH)));
+
+		assert_eq!(executed(&trades[0]), Some((1_000_000_000, 990_000_000_000_000_000_000)));
+	}
+
+	#[test]
+	fn fills_a_v3_exact_output_swap_whose_pools_pay_out_first() {
+		// The last pool pays out before the first is paid, through nested callbacks, but each
+		// pool logs its swap once it's paid, so the swaps still come in trade order
+		let receipt = receipt(vec![
+			transfer(WETH, 0x31, TRADER, 1_000_000_000_000_000_000),
+			transfer(DAI, 0x30, 0x31, 2_000_000_000_000_000_000_000),
+			transfer(USDC, TRADER, 0x30, 2_001_000_000),
+			v3_swap(0x30, 2_001_000_000, -2_000_000_000_000_000_000_000, 0x31),
+			v3_swap(0x31, 2_000_000_000_000_000_000_000, -1_000_000_000_000_000_000, TRADER),
+		]);
+		let mut trades = vec![trade(Asset::Token(address(USDC)), Asset::Token(address(WETH)), TradeKind::ExactOut)];
+		fill_executed(&mut trades, &receipt, Some(address(WETH)));
+
+		assert_eq!(executed(&trades[0]), Some((2_001_000_000, 1_000_000_000_000_000_000)));
+	}
+
+	#[test]
+	fn fills_two_trades_of_one_transaction_in_order() {
+		// ETH wrapped by the router and split between a V3 pool and a V2 pair
+		let receipt = receipt(vec![
+			log(WETH, &events().deposit, &[ROUTER], &[Token::Uint(U256::exp10(18))]),
+			transfer(USDC, 0x30, TRADER, 1_100_000_000),
+			transfer(WETH, ROUTER, 0x30, 600_000_000_000_000_000),
+			v3_swap(0x30, -1_100_000_000, 600_000_000_000_000_000, TRADER),
+			transfer(WETH, ROUTER, 0x20, 400_000_000_000_000_000),
+			transfer(USDC, 0x20, TRADER, 700_000_000),
+			v2_swap(0x20, (0, 400_000_000_000_000_000), (700_000_000, 0), TRADER),
+		]);
+		let mut trades = vec![
+			trade(Asset::Native, Asset::Token(address(USDC)), TradeKind::ExactIn),
+			trade(Asset::Native, Asset::Token(address(USDC)), TradeKind::ExactIn),
+		];
+		fill_executed(&mut trades, &receipt, Some(address(WETH)));
+
+		assert_eq!(executed(&trades[0]), Some((600_000_000_000_000_000, 1_100_000_000)));
+		assert_eq!(executed(&trades[1]), Some((400_000_000_000_000_000, 700_000_000)));
+	}
+
+	#[test]
+	fn leaves_trades_without_matching_swaps_unfilled() {
+		let receipt = receipt(vec![
+			transfer(USDC, TRADER, 0x20, 1_000_000_000),
+			v2_swap(0x20, (1_000_000_000, 0), (0, 500_000_000_000_000_000), TRADER),
+		]);
+		let mut trades = vec![trade(Asset::Token(address(DAI)), Asset::Token(address(WETH)), TradeKind::ExactIn)];
+		fill_executed(&mut trades, &receipt, Some(address(WETH)));
+
+		assert_eq!(trades[0].executed, None);
+	}
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use web3::types::{Address, Transaction, H256, U256};
+
+use crate::trade::Trade;
+
+/// A transaction seen in the mempool, whose trades are provisional until it's mined.
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+	pub tx: Transaction,
+	pub trades: Vec<Trade>,
+	/// When it was last seen pending
+	pub seen: Instant,
+}
+
+/// What became of a pending transaction once a block settled it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Settled {
+	/// Mined as the transaction given
+	Mined(Box<Transaction>),
+	/// Another transaction with the same sender and nonce was mined or entered the mempool instead
+	Replaced { by: H256 },
+}
+
+/// The pending transactions being followed, by hash and by sender and nonce, which is how a
+/// transaction that replaces one is told apart.
+#[derive(Debug, Default)]
+pub struct Mempool {
+	txs: HashMap<H256, PendingTx>,
+	nonces: HashMap<(Address, U256), H256>,
+}
+
+impl Mempool {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn len(&self) -> usize {
+		self.txs.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.txs.is_empty()
+	}
+
+	pub fn contains(&self, hash: H256) -> bool {
+		self.txs.contains_key(&hash)
+	}
+
+	/// Follows a pending transaction and its provisional trades. Returns the transaction it
+	/// replaces, if one with the same sender and nonce was being followed.
+	pub fn add(&mut self, tx: Transaction, trades: Vec<Trade>) -> Option<PendingTx> {
+		let replaced = tx.from
+			.and_then(|from| self.nonces.insert((from, tx.nonce), tx.hash))
+			.filter(|&hash| hash != tx.hash)
+			.and_then(|hash| self.txs.remove(&hash));
+		self.txs.insert(tx.hash, PendingTx { tx, trades, seen: Instant::now() });
+		replaced
+	}
+
+	/// Stops following a transaction.
+	pub fn remove(&mut self, hash: H256) -> Option<PendingTx> {
+		let pending = self.txs.remove(&hash)?;
+		if let Some(from) = pending.tx.from {
+			if self.nonces.get(&(from, pending.tx.nonce)) == Some(&hash) {
+				self.nonces.remove(&(from, pending.tx.nonce));
+			}
+		}
+		Some(pending)
+	}
+
+	/// Settles the transactions a new block mined, or replaced by mining another transaction
+	/// with the same sender and nonce, and stops following them.
+	pub fn mined(&mut self, block_txs: &[Transaction]) -> Vec<(PendingTx, Settled)> {
+		let mut settled: Vec<(PendingTx, Settled)> = Vec::new();
+		for tx in block_txs {
+			let hash = match tx.from.and_then(|from| self.nonces.get(&(from, tx.nonce))) {
+				Some(&hash) => hash,
+				None => continue,
+			};
+			if let Some(pending) = self.remove(hash) {
+				let outcome = if hash == tx.hash { Settled::Mined(Box::new(tx.clone())) } else { Settled::Replaced { by: tx.hash } };
+				settled.push((pending, outcome));
+			}
+		}
+		settled
+	}
+
+	/// Transactions that haven't been seen pending for `age`, to check whether they were dropped.
+	pub fn stale(&self, age: Duration) -> Vec<H256> {
+		self.txs.values().filter(|pending| pending.seen.elapsed() >= age).map(|pending| pending.tx.hash).collect()
+	}
+
+	/// Records that a transaction is still pending.
+	pub fn touch(&mut self, hash: H256) {
+		if let Some(pending) = self.txs.get_mut(&hash) {
+			pending.seen = Instant::now();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tx(hash: u64, from: u64, nonce: u64) -> Transaction {
+		Transaction {
+			hash: H256::from_low_u64_be(hash),
+			from: Some(Address::from_low_u64_be(from)),
+			nonce: U256::from(nonce),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn replaces_a_transaction_with_the_same_sender_and_nonce() {
+		let mut mempool = Mempool::new();
+		assert!(mempool.add(tx(1, 7, 0), Vec::new()).is_none());
+		assert!(mempool.add(tx(2, 7, 1), Vec::new()).is_none());
+		// Seeing the same transaction again replaces nothing
+		assert!(mempool.add(tx(1, 7, 0), Vec::new()).is_none());
+
+		let replaced = mempool.add(tx(3, 7, 0), Vec::new()).map(|pending| pending.tx.hash);
+		assert_eq!(replaced, Some(H256::from_low_u64_be(1)));
+		assert_eq!(mempool.len(), 2);
+		assert!(!mempool.contains(H256::from_low_u64_be(1)));
+	}
+
+	#[test]
+	fn settles_mined_and_replaced_transactions() {
+		let mut mempool = Mempool::new();
+		mempool.add(tx(1, 7, 0), Vec::new());
+		mempool.add(tx(2, 8, 0), Vec::new());
+		mempool.add(tx(3, 9, 0), Vec::new());
+
+		// Transactions nobody followed are ignored
+		let settled = mempool.mined(&[tx(1, 7, 0), tx(4, 8, 0), tx(5, 10, 0)]);
+		let settled: Vec<(H256, Settled)> = settled.into_iter().map(|(pending, outcome)| (pending.tx.hash, outcome)).collect();
+		assert_eq!(settled, vec![
+			(H256::from_low_u64_be(1), Settled::Mined(Box::new(tx(1, 7, 0)))),
+			(H256::from_low_u64_be(2), Settled::Replaced { by: H256::from_low_u64_be(4) }),
+		]);
+		assert_eq!(mempool.len(), 1);
+		assert!(mempool.contains(H256::from_low_u64_be(3)));
+		assert!(mempool.mined(&[tx(1, 7, 0)]).is_empty());
+	}
+
+	#[test]
+	fn removing_a_replaced_transaction_keeps_its_replacement() {
+		let mut mempool = Mempool::new();
+		mempool.add(tx(1, 7, 0), Vec::new());
+		mempool.add(tx(2, 7, 0), Vec::new());
+		assert!(mempool.remove(H256::from_low_u64_be(1)).is_none());
+
+		let settled = mempool.mined(&[tx(2, 7, 0)]);
+		assert_eq!(settled.len(), 1);
+		assert!(mempool.is_empty());
+	}
+
+	#[test]
+	fn stale_transactions_are_those_not_seen_for_the_age() {
+		let mut mempool = Mempool::new();
+		mempool.add(tx(1, 7, 0), Vec::new());
+		assert_eq!(mempool.stale(Duration::ZERO), vec![H256::from_low_u64_be(1)]);
+		mempool.touch(H256::from_low_u64_be(1));
+		assert!(mempool.stale(Duration::from_secs(60)).is_empty());
+	}
+}
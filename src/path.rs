@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use web3::types::Address;
+
+use crate::addresses::ChainAddresses;
+use crate::decoders::DecodeError;
+use crate::trade::TradeKind;
+
+const ADDRESS_SIZE: usize = 20;
+const FEE_SIZE: usize = 3;
+
+/// A swap through a single V3 pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hop {
+	pub token_in: Address,
+	pub token_out: Address,
+	/// Pool fee tier, in hundredths of a bip (500 is 0.05%).
+	pub fee: u32,
+}
+
+/// The route of a Uniswap V3 / PancakeSwap V3 multi-hop swap, in trade order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Path {
+	pub hops: Vec<Hop>,
+}
+
+impl Path {
+	/// Decodes a packed `address | uint24 fee | address ...` path.
+	///
+	/// Exact output swaps encode their path starting from the output token, so
+	/// their hops are reversed to read from the token in to the token out.
+	pub fn decode(path: &[u8], kind: TradeKind) -> Result<Self, DecodeError> {
+		let hop_size = FEE_SIZE + ADDRESS_SIZE;
+		if path.len() < ADDRESS_SIZE + hop_size || !(path.len() - ADDRESS_SIZE).is_multiple_of(hop_size) {
+			return Err(DecodeError::MalformedPath(path.to_vec()));
+		}
+
+		let mut hops: Vec<Hop> = Vec::new();
+		let mut offset = 0;
+		while offset + ADDRESS_SIZE < path.len() {
+			let token_a = Address::from_slice(&path[offset..offset + ADDRESS_SIZE]);
+			let fee_bytes = &path[offset + ADDRESS_SIZE..offset + hop_size];
+			let fee = u32::from_be_bytes([0, fee_bytes[0], fee_bytes[1], fee_bytes[2]]);
+			let token_b = Address::from_slice(&path[offset + hop_size..offset + hop_size + ADDRESS_SIZE]);
+			hops.push(match kind {
+				TradeKind::ExactIn => Hop { token_in: token_a, token_out: token_b, fee },
+				TradeKind::ExactOut => Hop { token_in: token_b, token_out: token_a, fee },
+			});
+			offset += hop_size;
+		}
+		if kind == TradeKind::ExactOut {
+			hops.reverse();
+		}
+
+		Ok(Self { hops })
+	}
+
+	/// Every token along the route, from the token in to the token out.
+	pub fn tokens(&self) -> Vec<Address> {
+		self.token_in().into_iter().chain(self.hops.iter().map(|hop| hop.token_out)).collect()
+	}
+
+	/// The token the route starts from, `None` for a route without hops.
+	pub fn token_in(&self) -> Option<Address> {
+		self.hops.first().map(|hop| hop.token_in)
+	}
+
+	/// The token the route ends at, `None` for a route without hops.
+	pub fn token_out(&self) -> Option<Address> {
+		self.hops.last().map(|hop| hop.token_out)
+	}
+
+	/// The route with token symbols from the address book, e.g. `usdc -(0.05%)-> weth`.
+	pub fn describe(&self, addresses: &ChainAddresses) -> String {
+		let mut route = self.token_in().map(|token| addresses.describe(token)).unwrap_or_default();
+		for hop in &self.hops {
+			route.push_str(&format!(" -({}%)-> {}", hop.fee as f64 / 10_000.0, addresses.describe(hop.token_out)));
+		}
+		route
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn token(byte: u8) -> Address {
+		Address::repeat_byte(byte)
+	}
+
+	/// `token_a | fee | token_b | fee | token_c ...`, as the routers take it.
+	fn packed(tokens: &[u8], fees: &[u32]) -> Vec<u8> {
+		let mut path = token(tokens[0]).as_bytes().to_vec();
+		for (next, fee) in tokens[1..].iter().zip(fees) {
+			path.extend(&fee.to_be_bytes()[1..]);
+			path.extend(token(*next).as_bytes());
+		}
+		path
+	}
+
+	#[test]
+	fn decodes_exact_input_paths_in_order() {
+		let path = Path::decode(&packed(&[1, 2, 3], &[500, 3000]), TradeKind::ExactIn).unwrap();
+		assert_eq!(path.hops, vec![
+			Hop { token_in: token(1), token_out: token(2), fee: 500 },
+			Hop { token_in: token(2), token_out: token(3), fee: 3000 },
+		]);
+		assert_eq!(path.tokens(), vec![token(1), token(2), token(3)]);
+	}
+
+	#[test]
+	fn reverses_exact_output_paths() {
+		// Exact output paths start from the token out, so this one sells token 3 for token 1
+		let path = Path::decode(&packed(&[1, 2, 3], &[500, 3000]), TradeKind::ExactOut).unwrap();
+		assert_eq!(path.hops, vec![
+			Hop { token_in: token(3), token_out: token(2), fee: 3000 },
+			Hop { token_in: token(2), token_out: token(1), fee: 500 },
+		]);
+		assert_eq!(path.token_in(), Some(token(3)));
+		assert_eq!(path.token_out(), Some(token(1)));
+	}
+
+	#[test]
+	fn rejects_malformed_lengths() {
+		let path = packed(&[1, 2], &[500]);
+		for length in [0, ADDRESS_SIZE, path.len() - 1, path.len() + FEE_SIZE] {
+			let mut malformed = path.clone();
+			malformed.resize(length, 0);
+			assert!(matches!(Path::decode(&malformed, TradeKind::ExactIn), Err(DecodeError::MalformedPath(_))), "length {}", length);
+		}
+	}
+
+	#[test]
+	fn a_route_without_hops_has_no_tokens() {
+		let path: Path = serde_json::from_str(r#"{"hops":[]}"#).unwrap();
+		assert_eq!(path.token_in(), None);
+		assert_eq!(path.token_out(), None);
+		assert!(path.tokens().is_empty());
+		assert_eq!(path.describe(&ChainAddresses::default()), "");
+	}
+}
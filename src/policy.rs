@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use web3::types::{Address, U256};
+
+use crate::addresses::ChainAddresses;
+use crate::trade::{Asset, Dex, Trade, TradeKind};
+
+/// Rules trades on one chain are checked against. Every rule is off by default.
+///
+/// The native coin is checked as the chain's wrapped native token, so allowing WETH
+/// allows ETH too.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Policy {
+	/// Tokens trades may swap, any token when unset
+	pub allowed_tokens: Option<HashSet<Address>>,
+	/// Tokens trades may never swap, checked even when the token is allowed
+	pub denied_tokens: HashSet<Address>,
+	/// DEXes trades may go through, any DEX when unset
+	pub allowed_dexes: Option<HashSet<Dex>>,
+	/// Largest amount of a token a trade may swap, in base units, as a decimal string or number,
+	/// e.g. `"1000000"` for 1 USDC
+	#[serde(with = "decimal_amounts")]
+	pub max_notional: HashMap<Address, U256>,
+	/// Whether trades must pay out to whoever sent them
+	pub recipient_must_be_sender: bool,
+}
+
+/// Reads and writes amounts by token in decimal, where `U256` on its own reads strings as hex.
+mod decimal_amounts {
+	use std::collections::HashMap;
+
+	use serde::de::Error;
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	use web3::types::{Address, U256};
+
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Amount {
+		Number(u64),
+		Decimal(String),
+	}
+
+	pub fn serialize<S: Serializer>(amounts: &HashMap<Address, U256>, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_map(amounts.iter().map(|(token, amount)| (token, amount.to_string())))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Address, U256>, D::Error> {
+		HashMap::<Address, Amount>::deserialize(deserializer)?
+			.into_iter()
+			.map(|(token, amount)| match amount {
+				Amount::Number(amount) => Ok((token, U256::from(amount))),
+				Amount::Decimal(amount) => U256::from_dec_str(&amount)
+					.map(|amount| (token, amount))
+					.map_err(|_| D::Error::custom(format!("malformed decimal amount {}", amount))),
+			})
+			.collect()
+	}
+}
+
+/// A rule a trade broke.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Violation {
+	DeniedToken(Asset),
+	TokenNotAllowed(Asset),
+	DexNotAllowed(Dex),
+	MaxNotionalExceeded { token: Asset, amount: U256, max: U256 },
+	RecipientNotSender { sender: Address, recipient: Address },
+}
+
+impl fmt::Display for Violation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let asset = |asset: &Asset| match asset {
+			Asset::Native => "native coin".to_owned(),
+			Asset::Token(token) => format!("{:?}", token),
+		};
+		match self {
+			Violation::DeniedToken(token) => write!(f, "token {} is denied", asset(token)),
+			Violation::TokenNotAllowed(token) => write!(f, "token {} is not allowed", asset(token)),
+			Violation::DexNotAllowed(dex) => write!(f, "dex {:?} is not allowed", dex),
+			Violation::MaxNotionalExceeded { token, amount, max } => write!(f, "swaps {} of {}, over the maximum of {}", amount, asset(token), max),
+			Violation::RecipientNotSender { sender, recipient } => write!(f, "pays out to {:?} instead of the sender {:?}", recipient, sender),
+		}
+	}
+}
+
+/// Whether a trade passed its chain's policy, and every rule it broke if not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+	pub allowed: bool,
+	pub violations: Vec<Violation>,
+}
+
+impl Policy {
+	pub fn evaluate(&self, trade: &Trade, addresses: &ChainAddresses) -> Verdict {
+		let token = |asset: Asset| match asset {
+			Asset::Native => addresses.wrapped_native(),
+			Asset::Token(token) => token,
+		};
+		// What was actually swapped when the receipt shows it, the most the calldata allows otherwise.
+		// The bound only caps the side the trader didn't fix, the other may be unknown until mined
+		let (bound_in, bound_out) = match trade.kind {
+			TradeKind::ExactIn => (None, Some(trade.bound)),
+			TradeKind::ExactOut => (Some(trade.bound), None),
+		};
+		let amount_in = trade.executed.map(|executed| executed.amount_in).or(trade.amount_in).or(bound_in);
+		let amount_out = trade.executed.map(|executed| executed.amount_out).or(trade.amount_out).or(bound_out);
+
+		let mut violations: Vec<Violation> = Vec::new();
+		for (asset, amount) in [(trade.token_in, amount_in), (trade.token_out, amount_out)] {
+			if self.denied_tokens.contains(&token(asset)) {
+				violations.push(Violation::DeniedToken(asset));
+			}
+			if self.allowed_tokens.as_ref().is_some_and(|allowed| !allowed.contains(&token(asset))) {
+				violations.push(Violation::TokenNotAllowed(asset));
+			}
+			if let (Some(max), Some(amount)) = (self.max_notional.get(&token(asset)), amount) {
+				if amount > *max {
+					violations.push(Violation::MaxNotionalExceeded { token: asset, amount, max: *max });
+				}
+			}
+		}
+		if self.allowed_dexes.as_ref().is_some_and(|allowed| !allowed.contains(&trade.dex)) {
+			violations.push(Violation::DexNotAllowed(trade.dex));
+		}
+		if self.recipient_must_be_sender && trade.recipient != trade.trader {
+			violations.push(Violation::RecipientNotSender { sender: trade.trader, recipient: trade.recipient });
+		}
+
+		Verdict { allowed: violations.is_empty(), violations }
+	}
+
+	/// Attaches a verdict to each trade.
+	pub fn apply(&self, trades: &mut [Trade], addresses: &ChainAddresses) {
+		for trade in trades.iter_mut() {
+			trade.verdict = Some(self.evaluate(trade, addresses));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::H256;
+
+	use super::*;
+	use crate::trade::{Chain, Executed, Protocol, TradeKind};
+
+	const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+	const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+	const TRADER: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+	fn address(address: &str) -> Address {
+		address.parse().unwrap()
+	}
+
+	fn addresses() -> ChainAddresses {
+		ChainAddresses {
+			wrapped_native: "weth".to_owned(),
+			tokens: [("weth".to_owned(), address(WETH)), ("usdc".to_owned(), address(USDC))].into_iter().collect(),
+			..Default::default()
+		}
+	}
+
+	fn policy(json: &str) -> Policy {
+		serde_json::from_str(json).unwrap()
+	}
+
+	/// Sells 1 ETH for at least 1,800 USDC on Uniswap.
+	fn trade() -> Trade {
+		Trade {
+			chain: Chain::Ethereum,
+			dex: Dex::Uniswap,
+			protocol: Protocol::V3,
+			tx_hash: H256::zero(),
+			trader: address(TRADER),
+			recipient: address(TRADER),
+			token_in: Asset::Native,
+			token_out: Asset::Token(address(USDC)),
+			amount_in: Some(U256::exp10(18)),
+			amount_out: None,
+			kind: TradeKind::ExactIn,
+			bound: U256::from(1_800_000_000u64),
+			path: Vec::new(),
+			route: None,
+			deadline: None,
+			executed: None,
+			verdict: None,
+		}
+	}
+
+	#[test]
+	fn allows_everything_by_default() {
+		assert_eq!(Policy::default().evaluate(&trade(), &addresses()), Verdict { allowed: true, violations: Vec::new() });
+	}
+
+	#[test]
+	fn checks_the_native_coin_as_the_wrapped_token() {
+		let allowed = policy(&format!(r#"{{"allowed-tokens": ["{}", "{}"]}}"#, WETH, USDC));
+		assert!(allowed.evaluate(&trade(), &addresses()).allowed);
+
+		let denied = policy(&format!(r#"{{"denied-tokens": ["{}"]}}"#, WETH));
+		assert_eq!(denied.evaluate(&trade(), &addresses()).violations, vec![Violation::DeniedToken(Asset::Native)]);
+
+		let usdc_only = policy(&format!(r#"{{"allowed-tokens": ["{}"]}}"#, USDC));
+		assert_eq!(usdc_only.evaluate(&trade(), &addresses()).violations, vec![Violation::TokenNotAllowed(Asset::Native)]);
+	}
+
+	#[test]
+	fn caps_amounts_with_decimal_max_notionals() {
+		// 1,000 USDC as a decimal string, which `U256` alone would read as hex, and 2 ETH as a number
+		let policy = policy(&format!(r#"{{"max-notional": {{"{}": "1000000000", "{}": 2000000000000000000}}}}"#, USDC, WETH));
+		assert_eq!(policy.max_notional.get(&address(USDC)), Some(&U256::from(1_000_000_000u64)));
+
+		// Without a receipt the minimum out is checked, with one what was executed
+		assert_eq!(policy.evaluate(&trade(), &addresses()).violations, vec![
+			Violation::MaxNotionalExceeded { token: Asset::Token(address(USDC)), amount: U256::from(1_800_000_000u64), max: U256::from(1_000_000_000u64) },
+		]);
+		let mut executed = trade();
+		executed.executed = Some(Executed { amount_in: U256::exp10(18), amount_out: U256::from(900_000_000u64) });
+		assert!(policy.evaluate(&executed, &addresses()).allowed);
+		executed.executed = Some(Executed { amount_in: U256::exp10(18) * 3, amount_out: U256::from(900_000_000u64) });
+		assert_eq!(policy.evaluate(&executed, &addresses()).violations, vec![
+			Violation::MaxNotionalExceeded { token: Asset::Native, amount: U256::exp10(18) * 3, max: U256::exp10(18) * 2 },
+		]);
+
+		// An amount in left to the router isn't known until the receipt shows it
+		let mut unknown = trade();
+		unknown.amount_in = None;
+		unknown.bound = U256::from(500_000_000u64);
+		assert!(policy.evaluate(&unknown, &addresses()).allowed);
+	}
+
+	#[test]
+	fn rejects_dexes_that_are_not_allowed() {
+		let policy = policy(r#"{"allowed-dexes": ["pancakeswap"]}"#);
+		assert_eq!(policy.evaluate(&trade(), &addresses()).violations, vec![Violation::DexNotAllowed(Dex::Uniswap)]);
+	}
+
+	#[test]
+	fn rejects_payouts_to_someone_else_when_required() {
+		let policy = policy(r#"{"recipient-must-be-sender": true}"#);
+		let mut trade = trade();
+		assert!(policy.evaluate(&trade, &addresses()).allowed);
+		trade.recipient = Address::from_low_u64_be(1);
+		assert_eq!(policy.evaluate(&trade, &addresses()).violations, vec![
+			Violation::RecipientNotSender { sender: address(TRADER), recipient: Address::from_low_u64_be(1) },
+		]);
+	}
+}
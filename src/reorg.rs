@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+use web3::types::H256;
+
+use crate::tracker::BlockTrades;
+
+/// The recent blocks a chain follower decoded that aren't final yet, so that when a reorg
+/// orphans them their trades can be retracted.
+///
+/// A block is final once it has `confirmations` confirmations, counting its own, so with
+/// one confirmation every block is final as soon as it's added.
+#[derive(Debug)]
+pub struct ReorgWindow {
+	confirmations: u64,
+	/// Oldest first, each the parent of the next
+	blocks: VecDeque<BlockTrades>,
+	/// Number and hash of the last block that was finalized
+	finalized: Option<(u64, H256)>,
+}
+
+impl ReorgWindow {
+	pub fn new(confirmations: u64) -> Self {
+		Self { confirmations: confirmations.max(1), blocks: VecDeque::new(), finalized: None }
+	}
+
+	pub fn confirmations(&self) -> u64 {
+		self.confirmations
+	}
+
+	/// Number and hash of the latest block added.
+	pub fn tip(&self) -> Option<(u64, H256)> {
+		self.blocks.back().map(|block| (block.number, block.hash)).or(self.finalized)
+	}
+
+	/// Whether a block with `parent_hash` builds on the tip. When it doesn't, the tip was orphaned.
+	pub fn extends(&self, parent_hash: H256) -> bool {
+		self.tip().is_none_or(|(_, hash)| hash == parent_hash)
+	}
+
+	/// Removes the orphaned tip to retract its trades, `None` if it's already final, in which
+	/// case the reorg is deeper than the confirmations the window waits for.
+	pub fn retract(&mut self) -> Option<BlockTrades> {
+		self.blocks.pop_back()
+	}
+
+	/// Adds a block building on the tip, returning the blocks that became final, oldest first.
+	pub fn push(&mut self, block: BlockTrades) -> Vec<BlockTrades> {
+		let tip = block.number;
+		self.blocks.push_back(block);
+
+		let mut finalized: Vec<BlockTrades> = Vec::new();
+		while let Some(block) = self.blocks.pop_front() {
+			if tip + 1 - block.number < self.confirmations {
+				self.blocks.push_front(block);
+				break;
+			}
+			self.finalized = Some((block.number, block.hash));
+			finalized.push(block);
+		}
+		finalized
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(number: u64) -> H256 {
+		H256::from_low_u64_be(number)
+	}
+
+	fn block(number: u64) -> BlockTrades {
+		BlockTrades { number, hash: hash(number), parent_hash: hash(number - 1), timestamp: 0, tx_count: 0, matched: 0, trades: Vec::new() }
+	}
+
+	fn numbers(blocks: &[BlockTrades]) -> Vec<u64> {
+		blocks.iter().map(|block| block.number).collect()
+	}
+
+	#[test]
+	fn finalizes_a_block_at_exactly_its_confirmations() {
+		let mut window = ReorgWindow::new(3);
+		assert!(window.push(block(10)).is_empty());
+		assert!(window.push(block(11)).is_empty());
+		assert_eq!(numbers(&window.push(block(12))), vec![10]);
+		assert_eq!(numbers(&window.push(block(13))), vec![11]);
+	}
+
+	#[test]
+	fn finalizes_every_block_with_one_confirmation() {
+		let mut window = ReorgWindow::new(0);
+		assert_eq!(window.confirmations(), 1);
+		assert_eq!(numbers(&window.push(block(10))), vec![10]);
+		assert_eq!(window.tip(), Some((10, hash(10))));
+	}
+
+	#[test]
+	fn retracts_two_orphaned_blocks_in_a_row() {
+		let mut window = ReorgWindow::new(4);
+		for number in 9..=11 {
+			assert!(window.push(block(number)).is_empty());
+		}
+
+		// A competing block 11 builds on a competing block 10, orphaning both
+		let fork_parent = H256::repeat_byte(0xaa);
+		assert!(!window.extends(fork_parent));
+		assert_eq!(window.retract().map(|block| block.number), Some(11));
+		assert!(!window.extends(fork_parent));
+		assert_eq!(window.retract().map(|block| block.number), Some(10));
+		assert!(window.extends(hash(9)));
+		assert_eq!(window.tip(), Some((9, hash(9))));
+	}
+
+	#[test]
+	fn cannot_retract_a_reorg_deeper_than_the_window() {
+		let mut window = ReorgWindow::new(2);
+		assert!(window.push(block(1)).is_empty());
+		assert_eq!(numbers(&window.push(block(2))), vec![1]);
+
+		assert_eq!(window.retract().map(|block| block.number), Some(2));
+		assert!(window.retract().is_none());
+		// The tip falls back to the last final block, which the reorg replaced too
+		assert_eq!(window.tip(), Some((1, hash(1))));
+		assert!(!window.extends(H256::repeat_byte(0xaa)));
+	}
+}
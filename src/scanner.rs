@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use web3::error::TransportError;
+use web3::{Transport, Web3};
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, H256, U64};
+
+use crate::fetch::FetchError;
+use crate::logs;
+
+/// Finds transactions by the logs they emitted with `eth_getLogs`, a window of blocks at a
+/// time, which is much faster than walking every transaction of every block.
+///
+/// Providers cap how many logs one query may return. When a window is rejected for that
+/// it's halved and retried, and it grows back towards its initial size once queries pass.
+/// A query rejected for the request rate is retried as is after a pause, a few times.
+pub struct LogScanner {
+	window: u64,
+	max_window: u64,
+	/// Each is queried on its own, as topics in different positions can't be OR-ed in one filter
+	filters: Vec<TopicFilter>,
+}
+
+/// How many times a rate limited query is retried before failing.
+const RATE_LIMIT_RETRIES: u32 = 5;
+/// Pause before retrying the first rate limited query, doubled on each retry.
+const RATE_LIMIT_DELAY: Duration = Duration::from_secs(1);
+
+/// Topics 0 to 2 of a log filter, `None` matching any topic.
+pub type TopicFilter = [Option<Vec<H256>>; 3];
+
+/// The swap transactions of a range of blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRange {
+	pub from_block: u64,
+	pub to_block: u64,
+	/// Transactions that emitted a matching log, once each, in chain order
+	pub tx_hashes: Vec<H256>,
+}
+
+impl LogScanner {
+	pub fn new(window: u64, filters: Vec<TopicFilter>) -> Self {
+		let window = window.max(1);
+		Self { window, max_window: window, filters }
+	}
+
+	/// Finds the transactions that emitted a V1, V2 or V3 swap.
+	pub fn swaps(window: u64) -> Self {
+		Self::new(window, vec![[Some(logs::swap_topics()), None, None]])
+	}
+
+	/// Finds the transactions that moved tokens from or to `wallet`, or swapped for it:
+	/// transfers it's a party to, V1 purchases it made and V2 / V3 swaps paying out to it.
+	pub fn wallet(window: u64, wallet: Address) -> Self {
+		let wallet = Some(vec![H256::from(wallet)]);
+		let topics = logs::wallet_topics();
+		Self::new(window, vec![
+			[Some(topics.sender), wallet.clone(), None],
+			[Some(topics.recipient), None, wallet],
+		])
+	}
+
+	/// Blocks the next query spans.
+	pub fn window(&self) -> u64 {
+		self.window
+	}
+
+	/// Queries the window starting at `from_block`, ending at `to_block` at the latest.
+	/// Fails when even a single block is rejected, when the provider stays rate limited,
+	/// or on any other error.
+	pub async fn next<T: Transport>(&mut self, web3: &Web3<T>, from_block: u64, to_block: u64) -> Result<LogRange, FetchError> {
+		let mut retries = 0;
+		'window: loop {
+			let end = to_block.min(from_block.saturating_add(self.window - 1));
+			let mut logs: Vec<Log> = Vec::new();
+			for [topic0, topic1, topic2] in &self.filters {
+				let filter = FilterBuilder::default()
+					.topics(topic0.clone(), topic1.clone(), topic2.clone(), None)
+					.from_block(BlockNumber::Number(U64::from(from_block)))
+					.to_block(BlockNumber::Number(U64::from(end)))
+					.build();
+				match web3.eth().logs(filter).await {
+					Ok(filter_logs) => logs.extend(filter_logs),
+					Err(e) => match rejection(&e) {
+						Rejection::TooLarge if self.window > 1 => {
+							self.window /= 2;
+							continue 'window;
+						},
+						Rejection::RateLimited if retries < RATE_LIMIT_RETRIES => {
+							tokio::time::sleep(RATE_LIMIT_DELAY * 2u32.pow(retries)).await;
+							retries += 1;
+							continue 'window;
+						},
+						_ => return Err(e.into()),
+					},
+				}
+			}
+
+			logs.sort_by_key(|log| (log.block_number, log.transaction_index));
+			let mut tx_hashes: Vec<H256> = logs.iter().filter_map(|log| log.transaction_hash).collect();
+			// A transaction may match more than once, and its logs are contiguous once sorted
+			tx_hashes.dedup();
+			self.window = self.max_window.min(self.window.saturating_mul(2));
+			return Ok(LogRange { from_block, to_block: end, tx_hashes });
+		}
+	}
+}
+
+/// Why a provider rejected a log query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+	/// The query spans too many blocks or returns too many logs
+	TooLarge,
+	/// Too many requests were made
+	RateLimited,
+	Other,
+}
+
+/// Messages of queries spanning too many blocks or returning too many logs, lower cased.
+const TOO_LARGE_MESSAGES: &[&str] = &[
+	// Infura
+	"query returned more than",
+	// Alchemy
+	"log response size exceeded",
+	// QuickNode
+	"is limited to a",
+	// Ankr
+	"block range is too wide",
+	// BSC and other geth forks
+	"exceed maximum block range",
+	// Erigon
+	"query exceeds max results",
+	"requested too many blocks",
+	"block range is too large",
+	"block range too large",
+];
+
+/// Messages of rate limited requests, lower cased.
+const RATE_LIMITED_MESSAGES: &[&str] = &[
+	"rate limit",
+	"request rate exceeded",
+	"too many requests",
+	"exceeded its compute units",
+	"limit exceeded",
+];
+
+/// Why the provider rejected a query. Providers word this differently, so it goes by the
+/// messages they're known to send. Infura's -32005 and HTTP 429 are also rate limits when the
+/// message doesn't say the query is too large.
+fn rejection(e: &web3::Error) -> Rejection {
+	let (code, message) = match e {
+		web3::Error::Rpc(e) => (Some(e.code.code()), e.message.to_lowercase()),
+		web3::Error::Transport(TransportError::Code(code)) => (Some(i64::from(*code)), String::new()),
+		web3::Error::Transport(TransportError::Message(message)) => (None, message.to_lowercase()),
+		_ => return Rejection::Other,
+	};
+	if TOO_LARGE_MESSAGES.iter().any(|pattern| message.contains(pattern)) {
+		Rejection::TooLarge
+	} else if matches!(code, Some(-32005 | 429)) || RATE_LIMITED_MESSAGES.iter().any(|pattern| message.contains(pattern)) {
+		Rejection::RateLimited
+	} else {
+		Rejection::Other
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rpc(code: i64, message: &str) -> web3::Error {
+		web3::Error::Rpc(serde_json::from_value(serde_json::json!({ "code": code, "message": message })).unwrap())
+	}
+
+	#[test]
+	fn recognizes_queries_that_are_too_large() {
+		for e in [
+			rpc(-32005, "query returned more than 10000 results"),
+			rpc(-32602, "Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range and no limit on the response size, or you can request any block range with a cap of 10K logs in the response. Based on your parameters, this block range should work: [0x10e0b40, 0x10e2f6b]"),
+			rpc(-32614, "eth_getLogs is limited to a 10,000 range"),
+			rpc(-32600, "block range is too wide"),
+			rpc(-32000, "exceed maximum block range: 5000"),
+			rpc(-32000, "query exceeds max results 20000, retry with the range 17000000-17000120"),
+			rpc(-32000, "block range too large"),
+		] {
+			assert_eq!(rejection(&e), Rejection::TooLarge, "{}", e);
+		}
+	}
+
+	#[test]
+	fn recognizes_rate_limits() {
+		for e in [
+			rpc(-32005, "daily request count exceeded, request rate limited"),
+			rpc(-32005, "project ID request rate exceeded"),
+			rpc(429, "Your app has exceeded its compute units per second capacity. If you have retries enabled, you can safely ignore this message. If not, check out https://docs.alchemy.com/reference/throughput"),
+			rpc(-32005, "limit exceeded"),
+			web3::Error::Transport(TransportError::Code(429)),
+		] {
+			assert_eq!(rejection(&e), Rejection::RateLimited, "{}", e);
+		}
+	}
+
+	#[test]
+	fn leaves_other_errors_alone() {
+		for e in [
+			rpc(-32602, "invalid params: fromBlock is greater than toBlock"),
+			rpc(-32000, "header not found"),
+			rpc(-32602, "invalid block range params"),
+			web3::Error::Transport(TransportError::Code(502)),
+			web3::Error::Unreachable,
+		] {
+			assert_eq!(rejection(&e), Rejection::Other, "{}", e);
+		}
+	}
+}
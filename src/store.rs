@@ -0,0 +1,542 @@
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+
+use web3::types::{Address, H256};
+
+use crate::export::{checksum, StepRecord, TradeRecord, TxRecord};
+use crate::tokens::TokenMetadata;
+use crate::trade::Chain;
+
+/// Schema changes in order, the database's `user_version` counting those applied.
+const MIGRATIONS: &[&str] = &[
+	"CREATE TABLE blocks (
+		chain INTEGER NOT NULL,
+		number INTEGER NOT NULL,
+		hash TEXT NOT NULL,
+		timestamp INTEGER,
+		PRIMARY KEY (chain, number)
+	);
+	CREATE TABLE tokens (
+		chain INTEGER NOT NULL,
+		address TEXT NOT NULL,
+		symbol TEXT NOT NULL,
+		name TEXT,
+		decimals INTEGER NOT NULL,
+		PRIMARY KEY (chain, address)
+	);
+	CREATE TABLE trades (
+		chain INTEGER NOT NULL,
+		tx_hash TEXT NOT NULL,
+		leg INTEGER NOT NULL,
+		status TEXT NOT NULL,
+		block_number INTEGER,
+		dex TEXT NOT NULL,
+		protocol TEXT NOT NULL,
+		trader TEXT NOT NULL,
+		recipient TEXT NOT NULL,
+		token_in TEXT NOT NULL,
+		token_out TEXT NOT NULL,
+		amount_in TEXT,
+		amount_out TEXT,
+		kind TEXT NOT NULL,
+		bound TEXT NOT NULL,
+		path TEXT NOT NULL,
+		fees TEXT NOT NULL,
+		deadline TEXT,
+		executed_amount_in TEXT,
+		executed_amount_out TEXT,
+		allowed INTEGER,
+		violations TEXT NOT NULL,
+		PRIMARY KEY (chain, tx_hash, leg)
+	);
+	CREATE INDEX trades_trader ON trades (chain, trader);
+	CREATE INDEX trades_block_number ON trades (chain, block_number);
+	CREATE TABLE steps (
+		chain INTEGER NOT NULL,
+		tx_hash TEXT NOT NULL,
+		step_index INTEGER NOT NULL,
+		command INTEGER NOT NULL,
+		name TEXT NOT NULL,
+		allow_revert INTEGER NOT NULL,
+		params TEXT NOT NULL,
+		PRIMARY KEY (chain, tx_hash, step_index)
+	);",
+	"CREATE TABLE checkpoints (
+		chain INTEGER NOT NULL,
+		job TEXT NOT NULL,
+		last_block INTEGER NOT NULL,
+		PRIMARY KEY (chain, job)
+	);
+	CREATE TABLE failed_ranges (
+		chain INTEGER NOT NULL,
+		job TEXT NOT NULL,
+		from_block INTEGER NOT NULL,
+		to_block INTEGER NOT NULL,
+		error TEXT NOT NULL,
+		attempts INTEGER NOT NULL,
+		PRIMARY KEY (chain, job, from_block, to_block)
+	);",
+];
+
+#[derive(Debug)]
+pub enum StoreError {
+	Sqlite(rusqlite::Error),
+	Json(serde_json::Error),
+	/// The database was migrated by a newer version of the tracker
+	UnsupportedVersion(i64),
+}
+
+impl fmt::Display for StoreError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			StoreError::Sqlite(e) => write!(f, "store error: {}", e),
+			StoreError::Json(e) => write!(f, "malformed stored value: {}", e),
+			StoreError::UnsupportedVersion(version) => write!(f, "unsupported store schema version {}", version),
+		}
+	}
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+	fn from(e: rusqlite::Error) -> Self {
+		StoreError::Sqlite(e)
+	}
+}
+
+impl From<serde_json::Error> for StoreError {
+	fn from(e: serde_json::Error) -> Self {
+		StoreError::Json(e)
+	}
+}
+
+/// A stored trade, with what's known of its transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredTrade {
+	/// Status of the transaction, as in `TxOutcome::status`
+	pub status: String,
+	pub block_number: Option<u64>,
+	/// Position of the trade among its transaction's trades
+	pub leg: usize,
+	pub trade: TradeRecord,
+}
+
+/// Blocks a scan job failed to process, to be retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRange {
+	pub from_block: u64,
+	pub to_block: u64,
+	/// The last error processing them
+	pub error: String,
+	/// How many times processing them failed
+	pub attempts: u32,
+}
+
+/// Filters for `Store::trades`, everything matching when left unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradeQuery {
+	pub chain: Option<Chain>,
+	pub tx_hash: Option<H256>,
+	/// Trades made by or paying out to the address
+	pub wallet: Option<Address>,
+	/// Trades selling or buying the token
+	pub token: Option<Address>,
+	pub status: Option<String>,
+	pub from_block: Option<u64>,
+	pub to_block: Option<u64>,
+	pub limit: Option<usize>,
+}
+
+/// Decoded trades, Universal Router steps, token metadata, processed blocks and the progress
+/// of scan jobs in SQLite.
+///
+/// Trades are keyed by chain, transaction hash and leg, and steps by chain, transaction hash
+/// and step index, so storing a transaction again, say once from a scan and once from the
+/// webhook, replaces its rows instead of adding to them.
+pub struct Store {
+	conn: Connection,
+}
+
+impl Store {
+	/// Opens the database at `path`, creating it if needed, and migrates it to the latest schema.
+	pub fn open(path: &Path) -> Result<Self, StoreError> {
+		Self::migrate(Connection::open(path)?)
+	}
+
+	/// A database that only lives as long as the store.
+	pub fn in_memory() -> Result<Self, StoreError> {
+		Self::migrate(Connection::open_in_memory()?)
+	}
+
+	fn migrate(mut conn: Connection) -> Result<Self, StoreError> {
+		let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+		if version > MIGRATIONS.len() as i64 {
+			return Err(StoreError::UnsupportedVersion(version));
+		}
+		for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+			let tx = conn.transaction()?;
+			tx.execute_batch(migration)?;
+			tx.pragma_update(None, "user_version", index as i64 + 1)?;
+			tx.commit()?;
+		}
+		Ok(Self { conn })
+	}
+
+	/// Stores what came of decoding a transaction. Its trades replace any stored before, and so
+	/// do its steps when it carries them: records written without steps, as from block scans,
+	/// leave those stored by `decode` in place. Outcomes without trades, such as a pending
+	/// transaction that was replaced or dropped, only update the status of the trades already stored.
+	pub fn insert_tx(&mut self, chain: Chain, record: &TxRecord) -> Result<(), StoreError> {
+		let tx = self.conn.transaction()?;
+		let chain_id = chain.id();
+		if record.trades.is_empty() && record.steps.is_empty() {
+			tx.execute(
+				"UPDATE trades SET status = ?3, block_number = coalesce(?4, block_number) WHERE chain = ?1 AND tx_hash = ?2",
+				params![chain_id, record.hash, record.status, record.block_number],
+			)?;
+			return Ok(tx.commit()?);
+		}
+
+		for (leg, trade) in record.trades.iter().enumerate() {
+			tx.execute(
+				"INSERT INTO trades (chain, tx_hash, leg, status, block_number, dex, protocol, trader, recipient, token_in, token_out,
+					amount_in, amount_out, kind, bound, path, deadline, executed_amount_in, executed_amount_out, allowed, violations, fees)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)
+				ON CONFLICT (chain, tx_hash, leg) DO UPDATE SET
+					status = excluded.status, block_number = coalesce(excluded.block_number, block_number), dex = excluded.dex,
+					protocol = excluded.protocol, trader = excluded.trader, recipient = excluded.recipient,
+					token_in = excluded.token_in, token_out = excluded.token_out, amount_in = excluded.amount_in,
+					amount_out = excluded.amount_out, kind = excluded.kind, bound = excluded.bound, path = excluded.path,
+					deadline = excluded.deadline, executed_amount_in = excluded.executed_amount_in,
+					executed_amount_out = excluded.executed_amount_out, allowed = excluded.allowed, violations = excluded.violations,
+					fees = excluded.fees",
+				params![
+					chain_id,
+					record.hash,
+					leg,
+					record.status,
+					record.block_number,
+					trade.dex,
+					trade.protocol,
+					trade.trader,
+					trade.recipient,
+					trade.token_in,
+					trade.token_out,
+					trade.amount_in,
+					trade.amount_out,
+					trade.kind,
+					trade.bound,
+					serde_json::to_string(&trade.path)?,
+					trade.deadline,
+					trade.executed_amount_in,
+					trade.executed_amount_out,
+					trade.allowed,
+					serde_json::to_string(&trade.violations)?,
+					serde_json::to_string(&trade.fees)?,
+				],
+			)?;
+		}
+		// A transaction decoded again into fewer trades leaves none of the old ones behind
+		tx.execute("DELETE FROM trades WHERE chain = ?1 AND tx_hash = ?2 AND leg >= ?3", params![chain_id, record.hash, record.trades.len()])?;
+
+		if record.steps.is_empty() {
+			return Ok(tx.commit()?);
+		}
+		for step in &record.steps {
+			tx.execute(
+				"INSERT INTO steps (chain, tx_hash, step_index, command, name, allow_revert, params) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+				ON CONFLICT (chain, tx_hash, step_index) DO UPDATE SET
+					command = excluded.command, name = excluded.name, allow_revert = excluded.allow_revert, params = excluded.params",
+				params![chain_id, record.hash, step.index, step.command, step.name, step.allow_revert, serde_json::to_string(&step.params)?],
+			)?;
+		}
+		tx.execute("DELETE FROM steps WHERE chain = ?1 AND tx_hash = ?2 AND step_index >= ?3", params![chain_id, record.hash, record.steps.len()])?;
+
+		Ok(tx.commit()?)
+	}
+
+	/// Records that a block was processed.
+	pub fn insert_block(&self, chain: Chain, number: u64, hash: H256, timestamp: Option<u64>) -> Result<(), StoreError> {
+		self.conn.execute(
+			"INSERT INTO blocks (chain, number, hash, timestamp) VALUES (?1, ?2, ?3, ?4)
+			ON CONFLICT (chain, number) DO UPDATE SET hash = excluded.hash, timestamp = coalesce(excluded.timestamp, timestamp)",
+			params![chain.id(), number, format!("{:?}", hash), timestamp],
+		)?;
+		Ok(())
+	}
+
+	pub fn insert_token(&self, chain: Chain, address: Address, token: &TokenMetadata) -> Result<(), StoreError> {
+		self.conn.execute(
+			"INSERT INTO tokens (chain, address, symbol, name, decimals) VALUES (?1, ?2, ?3, ?4, ?5)
+			ON CONFLICT (chain, address) DO UPDATE SET symbol = excluded.symbol, name = excluded.name, decimals = excluded.decimals",
+			params![chain.id(), checksum(address), token.symbol, token.name, token.decimals],
+		)?;
+		Ok(())
+	}
+
+	/// The last block a scan job processed, everything up to it having been processed or
+	/// recorded as failed.
+	pub fn checkpoint(&self, chain: Chain, job: &str) -> Result<Option<u64>, StoreError> {
+		Ok(self.conn.query_row(
+			"SELECT last_block FROM checkpoints WHERE chain = ?1 AND job = ?2",
+			params![chain.id(), job],
+			|row| row.get(0),
+		).optional()?)
+	}
+
+	pub fn set_checkpoint(&self, chain: Chain, job: &str, last_block: u64) -> Result<(), StoreError> {
+		self.conn.execute(
+			"INSERT INTO checkpoints (chain, job, last_block) VALUES (?1, ?2, ?3)
+			ON CONFLICT (chain, job) DO UPDATE SET last_block = excluded.last_block",
+			params![chain.id(), job, last_block],
+		)?;
+		Ok(())
+	}
+
+	/// Records blocks a scan job failed to process. Failing the same blocks again updates the
+	/// error and attempts.
+	pub fn insert_failed_range(&self, chain: Chain, job: &str, range: &FailedRange) -> Result<(), StoreError> {
+		self.conn.execute(
+			"INSERT INTO failed_ranges (chain, job, from_block, to_block, error, attempts) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+			ON CONFLICT (chain, job, from_block, to_block) DO UPDATE SET error = excluded.error, attempts = excluded.attempts",
+			params![chain.id(), job, range.from_block, range.to_block, range.error, range.attempts],
+		)?;
+		Ok(())
+	}
+
+	/// The blocks a scan job failed to process, oldest first.
+	pub fn failed_ranges(&self, chain: Chain, job: &str) -> Result<Vec<FailedRange>, StoreError> {
+		let mut statement = self.conn.prepare(
+			"SELECT from_block, to_block, error, attempts FROM failed_ranges WHERE chain = ?1 AND job = ?2 ORDER BY from_block",
+		)?;
+		let rows = statement.query_map(params![chain.id(), job], |row| {
+			Ok(FailedRange { from_block: row.get(0)?, to_block: row.get(1)?, error: row.get(2)?, attempts: row.get(3)? })
+		})?;
+		Ok(rows.collect::<Result<_, _>>()?)
+	}
+
+	pub fn remove_failed_range(&self, chain: Chain, job: &str, from_block: u64, to_block: u64) -> Result<(), StoreError> {
+		self.conn.execute(
+			"DELETE FROM failed_ranges WHERE chain = ?1 AND job = ?2 AND from_block = ?3 AND to_block = ?4",
+			params![chain.id(), job, from_block, to_block],
+		)?;
+		Ok(())
+	}
+
+	/// The stored trades matching `query`, in chain order.
+	pub fn trades(&self, query: &TradeQuery) -> Result<Vec<StoredTrade>, StoreError> {
+		let mut conditions: Vec<&str> = Vec::new();
+		let mut values: Vec<rusqlite::types::Value> = Vec::new();
+		if let Some(chain) = query.chain {
+			conditions.push("chain = ?");
+			values.push(i64::try_from(chain.id()).unwrap_or_default().into());
+		}
+		if let Some(hash) = query.tx_hash {
+			conditions.push("tx_hash = ?");
+			values.push(format!("{:?}", hash).into());
+		}
+		if let Some(wallet) = query.wallet {
+			conditions.push("(trader = ? OR recipient = ?)");
+			values.push(checksum(wallet).into());
+			values.push(checksum(wallet).into());
+		}
+		if let Some(token) = query.token {
+			conditions.push("(token_in = ? OR token_out = ?)");
+			values.push(checksum(token).into());
+			values.push(checksum(token).into());
+		}
+		if let Some(status) = &query.status {
+			conditions.push("status = ?");
+			values.push(status.clone().into());
+		}
+		if let Some(from_block) = query.from_block {
+			conditions.push("block_number >= ?");
+			values.push(i64::try_from(from_block).unwrap_or(i64::MAX).into());
+		}
+		if let Some(to_block) = query.to_block {
+			conditions.push("block_number <= ?");
+			values.push(i64::try_from(to_block).unwrap_or(i64::MAX).into());
+		}
+
+		let mut sql = String::from("SELECT * FROM trades");
+		if !conditions.is_empty() {
+			sql.push_str(" WHERE ");
+			sql.push_str(&conditions.join(" AND "));
+		}
+		sql.push_str(" ORDER BY chain, block_number, tx_hash, leg");
+		if let Some(limit) = query.limit {
+			sql.push_str(&format!(" LIMIT {}", limit));
+		}
+
+		let mut statement = self.conn.prepare(&sql)?;
+		let rows = statement.query_map(params_from_iter(values), stored_trade)?;
+		let mut trades: Vec<StoredTrade> = Vec::new();
+		for row in rows {
+			let (mut stored, path, fees, violations) = row?;
+			stored.trade.path = serde_json::from_str(&path)?;
+			stored.trade.fees = serde_json::from_str(&fees)?;
+			stored.trade.violations = serde_json::from_str(&violations)?;
+			trades.push(stored);
+		}
+		Ok(trades)
+	}
+
+	/// The stored Universal Router steps of a transaction, in order.
+	pub fn steps(&self, chain: Chain, tx_hash: H256) -> Result<Vec<StepRecord>, StoreError> {
+		let mut statement = self.conn.prepare(
+			"SELECT step_index, command, name, allow_revert, params FROM steps WHERE chain = ?1 AND tx_hash = ?2 ORDER BY step_index",
+		)?;
+		let rows = statement.query_map(params![chain.id(), format!("{:?}", tx_hash)], |row| {
+			let params: String = row.get(4)?;
+			Ok((StepRecord { index: row.get(0)?, command: row.get(1)?, name: row.get(2)?, allow_revert: row.get(3)?, params: Default::default() }, params))
+		})?;
+		let mut steps: Vec<StepRecord> = Vec::new();
+		for row in rows {
+			let (mut step, params) = row?;
+			step.params = serde_json::from_str(&params)?;
+			steps.push(step);
+		}
+		Ok(steps)
+	}
+
+	pub fn token(&self, chain: Chain, address: Address) -> Result<Option<TokenMetadata>, StoreError> {
+		Ok(self.conn.query_row(
+			"SELECT symbol, name, decimals FROM tokens WHERE chain = ?1 AND address = ?2",
+			params![chain.id(), checksum(address)],
+			|row| Ok(TokenMetadata { symbol: row.get(0)?, name: row.get(1)?, decimals: row.get(2)? }),
+		).optional()?)
+	}
+
+	/// The highest block processed on the chain.
+	pub fn last_block(&self, chain: Chain) -> Result<Option<u64>, StoreError> {
+		Ok(self.conn.query_row("SELECT max(number) FROM blocks WHERE chain = ?1", params![chain.id()], |row| row.get(0))?)
+	}
+}
+
+/// Reads a `SELECT * FROM trades` row, leaving its JSON `path`, `fees` and `violations` to be parsed.
+fn stored_trade(row: &Row) -> rusqlite::Result<(StoredTrade, String, String, String)> {
+	let chain_id: u64 = row.get("chain")?;
+	let trade = TradeRecord {
+		chain: Chain::from_id(chain_id).map(|chain| chain.to_string()).unwrap_or_else(|| chain_id.to_string()),
+		dex: row.get("dex")?,
+		protocol: row.get("protocol")?,
+		tx_hash: row.get("tx_hash")?,
+		trader: row.get("trader")?,
+		recipient: row.get("recipient")?,
+		token_in: row.get("token_in")?,
+		token_out: row.get("token_out")?,
+		amount_in: row.get("amount_in")?,
+		amount_out: row.get("amount_out")?,
+		kind: row.get("kind")?,
+		bound: row.get("bound")?,
+		path: Vec::new(),
+		fees: Vec::new(),
+		deadline: row.get("deadline")?,
+		executed_amount_in: row.get("executed_amount_in")?,
+		executed_amount_out: row.get("executed_amount_out")?,
+		allowed: row.get("allowed")?,
+		violations: Vec::new(),
+	};
+	let stored = StoredTrade { status: row.get("status")?, block_number: row.get("block_number")?, leg: row.get("leg")?, trade };
+	Ok((stored, row.get("path")?, row.get("fees")?, row.get("violations")?))
+}
+
+#[cfg(test)]
+mod tests {
+	use web3::types::U256;
+
+	use super::*;
+	use crate::batch::TxOutcome;
+	use crate::trade::{Asset, Dex, Protocol, Trade, TradeKind};
+
+	const HASH: &str = "0xabababababababababababababababababababababababababababababababab";
+
+	fn trade(amount_in: u64) -> Trade {
+		Trade {
+			chain: Chain::Ethereum,
+			dex: Dex::Uniswap,
+			protocol: Protocol::V2,
+			tx_hash: HASH.parse().unwrap(),
+			trader: Address::from_low_u64_be(7),
+			recipient: Address::from_low_u64_be(7),
+			token_in: Asset::Native,
+			token_out: Asset::Token(Address::from_low_u64_be(9)),
+			amount_in: Some(U256::from(amount_in)),
+			amount_out: None,
+			kind: TradeKind::ExactIn,
+			bound: U256::one(),
+			path: Vec::new(),
+			route: None,
+			deadline: None,
+			executed: None,
+			verdict: None,
+		}
+	}
+
+	fn record(hash: &str, outcome: TxOutcome, steps: usize) -> TxRecord {
+		let mut record = TxRecord::new(hash, &outcome, &[]);
+		record.steps = (0..steps)
+			.map(|index| StepRecord { index, command: 0, name: "V3_SWAP_EXACT_IN".to_owned(), allow_revert: false, params: Default::default() })
+			.collect();
+		record
+	}
+
+	fn statuses(store: &Store) -> Vec<(String, usize)> {
+		store.trades(&TradeQuery::default()).unwrap().into_iter().map(|stored| (stored.status, stored.leg)).collect()
+	}
+
+	#[test]
+	fn storing_a_transaction_again_replaces_its_rows() {
+		let mut store = Store::in_memory().unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Pending { trades: vec![trade(1), trade(2)] }, 2)).unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Success { trades: vec![trade(1)] }, 1)).unwrap();
+
+		assert_eq!(statuses(&store), vec![("success".to_owned(), 0)]);
+		assert_eq!(store.steps(Chain::Ethereum, HASH.parse().unwrap()).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn storing_a_transaction_without_steps_keeps_its_steps() {
+		let mut store = Store::in_memory().unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Success { trades: vec![trade(1)] }, 2)).unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Unconfirmed { trades: vec![trade(1)] }, 0)).unwrap();
+
+		assert_eq!(statuses(&store), vec![("unconfirmed".to_owned(), 0)]);
+		assert_eq!(store.steps(Chain::Ethereum, HASH.parse().unwrap()).unwrap().len(), 2);
+	}
+
+	#[test]
+	fn outcomes_without_trades_only_update_the_status() {
+		let mut store = Store::in_memory().unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Pending { trades: vec![trade(1)] }, 0)).unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Dropped, 0)).unwrap();
+
+		assert_eq!(statuses(&store), vec![("dropped".to_owned(), 0)]);
+	}
+
+	#[test]
+	fn mined_transactions_are_found_by_block_range() {
+		let mut store = Store::in_memory().unwrap();
+		let outcome = TxOutcome::Success { trades: vec![trade(1)] };
+		store.insert_tx(Chain::Ethereum, &TxRecord::new(HASH, &outcome, &[]).mined_in(Some(17_000_000))).unwrap();
+
+		let query = |from_block, to_block| TradeQuery { from_block: Some(from_block), to_block: Some(to_block), ..Default::default() };
+		let found = store.trades(&query(16_999_990, 17_000_000)).unwrap();
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].block_number, Some(17_000_000));
+		assert!(store.trades(&query(17_000_001, 17_000_010)).unwrap().is_empty());
+	}
+
+	#[test]
+	fn hashes_are_stored_once_whatever_their_case() {
+		let mut store = Store::in_memory().unwrap();
+		store.insert_tx(Chain::Ethereum, &record(HASH, TxOutcome::Success { trades: vec![trade(1)] }, 0)).unwrap();
+		store.insert_tx(Chain::Ethereum, &record(&HASH.to_uppercase().replace("0X", "0x"), TxOutcome::Success { trades: vec![trade(1)] }, 0)).unwrap();
+
+		let query = TradeQuery { tx_hash: Some(HASH.parse().unwrap()), ..Default::default() };
+		assert_eq!(store.trades(&query).unwrap().len(), 1);
+		assert_eq!(statuses(&store).len(), 1);
+	}
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use web3::ethabi::{self, Contract, ParamType, Token};
+use web3::{Transport, Web3};
+use web3::types::{Address, Bytes, CallRequest, U256};
+
+use crate::abi;
+use crate::trade::{Asset, Chain, Trade, TradeKind};
+
+#[derive(Debug)]
+pub enum ResolveError {
+	Rpc(web3::Error),
+	Abi(ethabi::Error),
+	/// The contract doesn't answer `decimals()`, so amounts of it can't be scaled
+	NotAToken(Address),
+	Io(std::io::Error),
+	Cache(serde_json::Error),
+}
+
+impl fmt::Display for ResolveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ResolveError::Rpc(e) => write!(f, "rpc error: {}", e),
+			ResolveError::Abi(e) => write!(f, "failed to decode token metadata: {}", e),
+			ResolveError::NotAToken(address) => write!(f, "{:?} is not an ERC-20 token", address),
+			ResolveError::Io(e) => write!(f, "failed to access token cache: {}", e),
+			ResolveError::Cache(e) => write!(f, "malformed token cache: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for ResolveError {}
+
+impl From<web3::Error> for ResolveError {
+	fn from(e: web3::Error) -> Self {
+		ResolveError::Rpc(e)
+	}
+}
+
+impl From<ethabi::Error> for ResolveError {
+	fn from(e: ethabi::Error) -> Self {
+		ResolveError::Abi(e)
+	}
+}
+
+impl From<std::io::Error> for ResolveError {
+	fn from(e: std::io::Error) -> Self {
+		ResolveError::Io(e)
+	}
+}
+
+impl From<serde_json::Error> for ResolveError {
+	fn from(e: serde_json::Error) -> Self {
+		ResolveError::Cache(e)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+	pub symbol: String,
+	/// Some tokens don't implement the optional `name()`
+	pub name: Option<String>,
+	pub decimals: u8,
+}
+
+impl TokenMetadata {
+	/// The chain's native coin, which has no contract to ask.
+	pub fn native(chain: Chain) -> Self {
+		let (symbol, name) = match chain {
+			Chain::Ethereum => ("ETH", "Ether"),
+			Chain::Bsc => ("BNB", "BNB"),
+		};
+		Self { symbol: symbol.to_owned(), name: Some(name.to_owned()), decimals: 18 }
+	}
+
+	/// An amount in base units with its symbol, e.g. `1,234.56 USDC`.
+	pub fn format(&self, amount: U256) -> String {
+		format!("{} {}", format_amount(amount, self.decimals), self.symbol)
+	}
+}
+
+/// Scales an amount in base units by `decimals`, with thousands separators and no trailing zeros.
+pub fn format_amount(amount: U256, decimals: u8) -> String {
+	let digits = amount.to_string();
+	let decimals = decimals as usize;
+	let (whole, fraction) = if digits.len() > decimals {
+		let (whole, fraction) = digits.split_at(digits.len() - decimals);
+		(whole.to_owned(), fraction.to_owned())
+	} else {
+		("0".to_owned(), format!("{:0>width$}", digits, width = decimals))
+	};
+
+	let mut grouped = String::new();
+	for (index, digit) in whole.chars().enumerate() {
+		if index > 0 && (whole.len() - index).is_multiple_of(3) {
+			grouped.push(',');
+		}
+		grouped.push(digit);
+	}
+
+	let fraction = fraction.trim_end_matches('0');
+	if fraction.is_empty() {
+		grouped
+	} else {
+		format!("{}.{}", grouped, fraction)
+	}
+}
+
+/// Reads a string returned by `symbol()` or `name()`, which some older tokens (e.g. MKR)
+/// return as a null padded `bytes32` instead.
+fn decode_string(output: &[u8]) -> Option<String> {
+	if let Ok(mut tokens) = ethabi::decode(&[ParamType::String], output) {
+		return tokens.pop().and_then(Token::into_string);
+	}
+	if output.len() == 32 {
+		let end = output.iter().position(|byte| *byte == 0).unwrap_or(output.len());
+		return Some(String::from_utf8_lossy(&output[..end]).into_owned());
+	}
+	None
+}
+
+/// Whether a failed `eth_call` reverted, which says the contract lacks the function. Other
+/// errors, such as rate limits or a node missing the state, say nothing about the token.
+fn is_revert(e: &web3::Error) -> bool {
+	match e {
+		// Geth answers reverts carrying data with code 3, and says so in the message otherwise
+		web3::Error::Rpc(e) => {
+			let message = e.message.to_lowercase();
+			e.code.code() == 3 || message.contains("revert") || message.contains("invalid opcode")
+		},
+		_ => false,
+	}
+}
+
+/// Looks up token symbols, names and decimals with `eth_call`, remembering them in a
+/// per chain JSON file so each token is only asked once.
+pub struct TokenResolver<T: Transport> {
+	web3: Web3<T>,
+	chain: Chain,
+	contract: Contract,
+	cache_path: PathBuf,
+	cache: HashMap<Address, TokenMetadata>,
+}
+
+impl<T: Transport> TokenResolver<T> {
+	/// Creates a resolver caching to `<cache_dir>/<chain>.json`, loading what's already cached there.
+	pub fn new(web3: Web3<T>, chain: Chain, cache_dir: &Path) -> Result<Self, ResolveError> {
+		let cache_path = cache_dir.join(format!("{}.json", chain));
+		let cache = if cache_path.exists() {
+			serde_json::from_str(&fs::read_to_string(&cache_path)?)?
+		} else {
+			HashMap::new()
+		};
+
+		Ok(Self {
+			web3,
+			chain,
+			contract: abi::load(abi::ERC20)?,
+			cache_path,
+			cache,
+		})
+	}
+
+	async fn call(&self, token: Address, function_name: &str) -> Result<Option<Vec<u8>>, ResolveError> {
+		let data = self.contract.function(function_name)?.encode_input(&[])?;
+		let request = CallRequest::builder().to(token).data(Bytes(data)).build();
+		match self.web3.eth().call(request, None).await {
+			Ok(output) if !output.0.is_empty() => Ok(Some(output.0)),
+			// Calls to missing functions revert, or return nothing from contracts without a fallback
+			Ok(_) => Ok(None),
+			Err(e) if is_revert(&e) => Ok(None),
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	pub async fn resolve(&mut self, token: Address) -> Result<TokenMetadata, ResolveError> {
+		if let Some(metadata) = self.cache.get(&token) {
+			return Ok(metadata.clone());
+		}
+
+		// Both uint8 and the uint256 some tokens (e.g. USDT) declare are padded to a full word
+		let decimals = self.call(token, "decimals").await?
+			.and_then(|output| ethabi::decode(&[ParamType::Uint(256)], &output).ok())
+			.and_then(|mut tokens| tokens.pop())
+			.and_then(Token::into_uint)
+			.filter(|decimals| *decimals <= U256::from(u8::MAX))
+			.ok_or(ResolveError::NotAToken(token))?;
+		let symbol = self.call(token, "symbol").await?.and_then(|output| decode_string(&output));
+		let name = self.call(token, "name").await?.and_then(|output| decode_string(&output));
+
+		let metadata = TokenMetadata {
+			symbol: symbol.unwrap_or_else(|| format!("{:?}", token)),
+			name,
+			decimals: decimals.as_u32() as u8,
+		};
+		self.cache.insert(token, metadata.clone());
+		self.save()?;
+		Ok(metadata)
+	}
+
+	pub async fn resolve_asset(&mut self, asset: Asset) -> Result<TokenMetadata, ResolveError> {
+		match asset {
+			Asset::Native => Ok(TokenMetadata::native(self.chain)),
+			Asset::Token(token) => self.resolve(token).await,
+		}
+	}
+
+	fn save(&self) -> Result<(), ResolveError> {
+		if let Some(dir) = self.cache_path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(&self.cache_path, serde_json::to_string_pretty(&self.cache)?)?;
+		Ok(())
+	}
+
+	/// The trade as a sentence with human amounts, e.g.
+	/// `Sell 1,234.56 USDC for at least 0.5 WETH, executed 1,234.56 USDC for 0.51 WETH`.
+	/// An amount the calldata leaves to the router is left out, as in `Sell USDC for ...`.
+	pub async fn describe(&mut self, trade: &Trade) -> Result<String, ResolveError> {
+		let token_in = self.resolve_asset(trade.token_in).await?;
+		let token_out = self.resolve_asset(trade.token_out).await?;
+		let format = |token: &TokenMetadata, amount: Option<U256>| amount.map_or_else(|| token.symbol.clone(), |amount| token.format(amount));
+
+		let mut description = match trade.kind {
+			TradeKind::ExactIn => format!("Sell {} for at least {}", format(&token_in, trade.amount_in), token_out.format(trade.bound)),
+			TradeKind::ExactOut => format!("Buy {} for at most {}", format(&token_out, trade.amount_out), token_in.format(trade.bound)),
+		};
+		if let Some(executed) = trade.executed {
+			description.push_str(&format!(", executed {} for {}", token_in.format(executed.amount_in), token_out.format(executed.amount_out)));
+		}
+		Ok(description)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rpc_error(code: i64, message: &str) -> web3::Error {
+		web3::Error::Rpc(serde_json::from_value(serde_json::json!({ "code": code, "message": message })).unwrap())
+	}
+
+	#[test]
+	fn only_reverts_mean_a_function_is_missing() {
+		assert!(is_revert(&rpc_error(-32000, "execution reverted")));
+		assert!(is_revert(&rpc_error(3, "execution reverted: function selector was not recognized")));
+		assert!(is_revert(&rpc_error(-32015, "VM execution error: Reverted 0x")));
+		assert!(is_revert(&rpc_error(-32000, "invalid opcode: INVALID")));
+
+		assert!(!is_revert(&rpc_error(-32005, "daily request count exceeded, request rate limited")));
+		assert!(!is_revert(&rpc_error(-32000, "header not found")));
+		assert!(!is_revert(&rpc_error(-32000, "missing trie node 5d1b... (path )")));
+		assert!(!is_revert(&web3::Error::Unreachable));
+	}
+}
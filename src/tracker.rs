@@ -0,0 +1,215 @@
+use std::fmt;
+
+use web3::{Transport, Web3};
+use web3::types::{Transaction, TransactionReceipt, H256, U64};
+
+use crate::addresses::{AddressBook, ChainAddresses};
+use crate::decoders::universal_router::{Step, UniversalRouterDecoder};
+use crate::decoders::{DecodeError, DexDecoder, Registry};
+use crate::factory::{FactoryError, UniswapV1Factory};
+use crate::fetch::{fetch_block, fetch_receipt, fetch_tx, FetchError};
+use crate::flow;
+use crate::policy::Policy;
+use crate::trade::{Chain, Trade};
+
+#[derive(Debug)]
+pub enum TrackError {
+	Fetch(FetchError),
+	Decode(DecodeError),
+	Factory(FactoryError),
+	/// The address book has no entry for the chain
+	UnknownChain(Chain),
+}
+
+impl fmt::Display for TrackError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TrackError::Fetch(e) => write!(f, "{}", e),
+			TrackError::Decode(e) => write!(f, "{}", e),
+			TrackError::Factory(e) => write!(f, "{}", e),
+			TrackError::UnknownChain(chain) => write!(f, "chain {} is not in the address book", chain),
+		}
+	}
+}
+
+impl std::error::Error for TrackError {}
+
+impl From<FetchError> for TrackError {
+	fn from(e: FetchError) -> Self {
+		TrackError::Fetch(e)
+	}
+}
+
+impl From<DecodeError> for TrackError {
+	fn from(e: DecodeError) -> Self {
+		TrackError::Decode(e)
+	}
+}
+
+impl From<FactoryError> for TrackError {
+	fn from(e: FactoryError) -> Self {
+		TrackError::Factory(e)
+	}
+}
+
+/// The trades of one block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTrades {
+	pub number: u64,
+	pub hash: H256,
+	pub parent_hash: H256,
+	pub timestamp: u64,
+	/// Transactions in the block
+	pub tx_count: usize,
+	/// Transactions sent to a contract with a decoder
+	pub matched: usize,
+	pub trades: Vec<Trade>,
+}
+
+/// Everything needed to turn transactions on one chain into checked trades.
+pub struct Tracker<T: Transport> {
+	pub chain: Chain,
+	pub web3: Web3<T>,
+	pub addresses: ChainAddresses,
+	pub registry: Registry,
+	pub policy: Policy,
+	uniswap_v1_factory: Option<UniswapV1Factory<T>>,
+	universal_router: Option<UniversalRouterDecoder>,
+}
+
+impl<T: Transport> Tracker<T> {
+	pub fn new(chain: Chain, web3: Web3<T>, address_book: &AddressBook, policy: Policy) -> Result<Self, TrackError> {
+		let addresses = address_book.chain(chain).ok_or(TrackError::UnknownChain(chain))?.clone();
+		let uniswap_v1_factory = match addresses.factories.get("uniswap-v1") {
+			Some(factory) => Some(UniswapV1Factory::new(web3.clone(), *factory)?),
+			None => None,
+		};
+		let universal_router = match addresses.routers.get("uniswap-universal-router") {
+			Some(router) => Some(UniversalRouterDecoder::new(chain, *router, addresses.wrapped_native())?),
+			None => None,
+		};
+
+		Ok(Self {
+			chain,
+			web3,
+			registry: Registry::from_address_book(address_book)?,
+			addresses,
+			policy,
+			uniswap_v1_factory,
+			universal_router,
+		})
+	}
+
+	/// Loads every Uniswap V1 exchange created since `from_block` from the factory's events.
+	pub async fn index_uniswap_v1(&self, from_block: u64) -> Result<usize, TrackError> {
+		let (factory, decoder) = match (&self.uniswap_v1_factory, self.registry.uniswap_v1(self.chain)) {
+			(Some(factory), Some(decoder)) => (factory, decoder),
+			_ => return Ok(0),
+		};
+		let latest_block = self.web3.eth().block_number().await.map_err(FetchError::from)?;
+		let exchanges = factory.index(from_block, latest_block.as_u64(), 50_000).await?;
+		for (exchange, token) in &exchanges {
+			decoder.add_exchange(*exchange, *token);
+		}
+		Ok(exchanges.len())
+	}
+
+	/// Adds the Uniswap V1 exchanges `tx` trades through that the registry doesn't know yet.
+	/// Failing to look them up is only reported, so `tx` is still decoded with the exchanges known.
+	pub async fn discover(&self, tx: &Transaction) {
+		if let Some(factory) = &self.uniswap_v1_factory {
+			if let Err(e) = factory.discover(&self.registry, self.chain, tx).await {
+				eprintln!("Error: failed to look up Uniswap V1 exchanges: {}", e);
+			}
+		}
+	}
+
+	/// Name of the decoder for `tx`, `None` if it goes to a contract without one.
+	pub fn decoder_name(&self, tx: &Transaction) -> Option<&str> {
+		tx.to.and_then(|to| self.registry.find(to, self.chain)).map(|decoder| decoder.name())
+	}
+
+	/// The commands of a call to the Universal Router, empty for transactions to anything else.
+	pub fn steps(&self, tx: &Transaction) -> Vec<Step> {
+		match &self.universal_router {
+			Some(router) if tx.to.is_some_and(|to| router.matches(to, self.chain)) => router.steps(tx).unwrap_or_default(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Decodes a mined transaction, reconstructing the trade from token flows when it went
+	/// to a contract without a decoder, and checks the trades against the policy.
+	pub async fn decode(&self, tx: &Transaction, receipt: &TransactionReceipt) -> Result<Vec<Trade>, TrackError> {
+		self.discover(tx).await;
+
+		let mut trades = match tx.to.and_then(|to| self.registry.find(to, self.chain)) {
+			Some(decoder) => decoder.decode(tx, receipt)?,
+			None => flow::trade(self.chain, tx, receipt).into_iter().collect(),
+		};
+		self.policy.apply(&mut trades, &self.addresses);
+		Ok(trades)
+	}
+
+	/// Decodes a pending transaction from its input alone, so its trades are provisional and
+	/// have nothing executed. Transactions to contracts without a decoder can't be decoded,
+	/// as there are no token flows to reconstruct their trades from yet.
+	pub async fn decode_pending(&self, tx: &Transaction) -> Result<Vec<Trade>, TrackError> {
+		self.discover(tx).await;
+
+		let decoder = tx.to
+			.and_then(|to| self.registry.find(to, self.chain))
+			.ok_or(DecodeError::NoDecoder(tx.to))?;
+		let mut trades = decoder.decode_input(tx)?;
+		self.policy.apply(&mut trades, &self.addresses);
+		Ok(trades)
+	}
+
+	/// Fetches a transaction by hash and decodes it, failing if it was reverted. Pending
+	/// transactions, without a `block_number`, are decoded from their input.
+	pub async fn decode_hash(&self, hash: &str) -> Result<(Transaction, Vec<Trade>), TrackError> {
+		let (tx, receipt) = fetch_tx(&self.web3, hash).await?;
+		let trades = match receipt {
+			Some(receipt) => self.decode(&tx, &receipt).await?,
+			None => self.decode_pending(&tx).await?,
+		};
+		Ok((tx, trades))
+	}
+
+	/// Decodes a transaction received from elsewhere, fetching its receipt if it was mined.
+	/// Pending transactions are decoded from their input alone.
+	pub async fn decode_tx(&self, tx: &Transaction) -> Result<Vec<Trade>, TrackError> {
+		match fetch_receipt(&self.web3, tx).await? {
+			Some(receipt) => self.decode(tx, &receipt).await,
+			None => self.decode_pending(tx).await,
+		}
+	}
+
+	/// Decodes every successful transaction in a block that was sent to a contract with a decoder,
+	/// skipping those it fails to decode. Receipts are only fetched for those transactions, once
+	/// the Uniswap V1 exchanges they may call are looked up.
+	pub async fn decode_block(&self, number: u64) -> Result<BlockTrades, TrackError> {
+		let block = fetch_block(&self.web3, number).await?;
+		let (hash, parent_hash, timestamp, txs) = (block.hash.unwrap_or_default(), block.parent_hash, block.timestamp.as_u64(), block.transactions);
+		let tx_count = txs.len();
+		let mut matched = 0;
+		let mut trades: Vec<Trade> = Vec::new();
+		for tx in txs {
+			self.discover(&tx).await;
+			if self.decoder_name(&tx).is_none() {
+				continue;
+			}
+			matched += 1;
+			let receipt = match self.web3.eth().transaction_receipt(tx.hash).await.map_err(FetchError::from)? {
+				Some(receipt) if receipt.status == Some(U64::from(1)) => receipt,
+				_ => continue,
+			};
+			// One transaction the decoder can't make sense of doesn't stop the rest of the block
+			match self.decode(&tx, &receipt).await {
+				Ok(tx_trades) => trades.extend(tx_trades),
+				Err(TrackError::Decode(_)) => continue,
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(BlockTrades { number, hash, parent_hash, timestamp, tx_count, matched, trades })
+	}
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use web3::types::{Address, H256, U256};
+
+use crate::path::Path;
+use crate::policy::Verdict;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Chain {
+	#[serde(rename = "eth")]
+	Ethereum,
+	#[serde(rename = "bsc")]
+	Bsc,
+}
+
+impl Chain {
+	pub const ALL: [Chain; 2] = [Chain::Ethereum, Chain::Bsc];
+
+	/// EIP-155 chain id.
+	pub fn id(self) -> u64 {
+		match self {
+			Chain::Ethereum => 1,
+			Chain::Bsc => 56,
+		}
+	}
+
+	pub fn from_id(id: u64) -> Option<Self> {
+		match id {
+			1 => Some(Chain::Ethereum),
+			56 => Some(Chain::Bsc),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for Chain {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Chain::Ethereum => write!(f, "eth"),
+			Chain::Bsc => write!(f, "bsc"),
+		}
+	}
+}
+
+/// Parses a chain from its short name or chain id, e.g. `eth` or `1`.
+impl FromStr for Chain {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"eth" | "ethereum" => Ok(Chain::Ethereum),
+			"bsc" => Ok(Chain::Bsc),
+			_ => s.parse().ok()
+				.and_then(Chain::from_id)
+				.ok_or_else(|| format!("unsupported chain {}", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dex {
+	Uniswap,
+	PancakeSwap,
+	Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+	V1,
+	V2,
+	V3,
+	StableSwap,
+	Unknown,
+}
+
+/// Either the chain's native coin (ETH, BNB) or an ERC-20 token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Asset {
+	Native,
+	Token(Address),
+}
+
+/// Which side of the trade the trader fixed, the other side is bounded by slippage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeKind {
+	ExactIn,
+	ExactOut,
+}
+
+impl TradeKind {
+	/// Places the fixed `amount` on the side of the trade this kind fixes, as `(amount_in, amount_out)`.
+	pub fn amounts(self, amount: U256) -> (Option<U256>, Option<U256>) {
+		match self {
+			TradeKind::ExactIn => (Some(amount), None),
+			TradeKind::ExactOut => (None, Some(amount)),
+		}
+	}
+}
+
+/// What a trade actually swapped, read from the swap events in its receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Executed {
+	pub amount_in: U256,
+	pub amount_out: U256,
+}
+
+/// A single swap, as requested by the transaction's calldata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trade {
+	pub chain: Chain,
+	pub dex: Dex,
+	pub protocol: Protocol,
+	pub tx_hash: H256,
+	pub trader: Address,
+	pub recipient: Address,
+	pub token_in: Asset,
+	pub token_out: Asset,
+	/// Known up front for `ExactIn` trades, unless the router swaps whatever an earlier
+	/// call left it.
+	pub amount_in: Option<U256>,
+	/// Known up front for `ExactOut` trades.
+	pub amount_out: Option<U256>,
+	pub kind: TradeKind,
+	/// Minimum amount out for `ExactIn` trades, maximum amount in for `ExactOut` trades.
+	pub bound: U256,
+	/// Tokens the swap routes through, in trade order. Empty for protocols without routing.
+	pub path: Vec<Address>,
+	/// The V3 pools the swap routes through with their fee tiers, for V3 swaps.
+	pub route: Option<Path>,
+	/// Unix timestamp after which the trade reverts, if the call sets one.
+	pub deadline: Option<U256>,
+	/// Amounts swapped on chain, when the receipt's swap events could be matched to the trade.
+	pub executed: Option<Executed>,
+	/// Set once the trade has been checked against a policy.
+	pub verdict: Option<Verdict>,
+}